@@ -30,16 +30,25 @@ pub async fn submit_proof(
         threshold: req.public_inputs.threshold,
         old_state_root: req.public_inputs.old_state_root,
         nonce: req.public_inputs.nonce,
+        payload_commitment: req.public_inputs.payload_commitment,
+        dataset_root: req.public_inputs.dataset_root,
     };
 
     let commitment = Commitment(req.new_state_commitment);
 
-    engine
+    let receipt = engine
         .process_transition(&proof_bytes, inputs, commitment)
         .map_err(map_error)?;
 
     Ok(Json(SubmitProofResponse {
         status: "accepted".to_string(),
+        previous_state: receipt.previous_state,
+        new_state: receipt.new_state,
+        proof_hash: receipt.proof_hash,
+        transition_hash: receipt.transition_hash,
+        backend: receipt.backend.to_string(),
+        policy: receipt.policy.name().to_string(),
+        verification_micros: receipt.duration.as_micros() as u64,
     }))
 }
 
@@ -53,5 +62,6 @@ fn map_error(err: ProtocolError) -> (StatusCode, String) {
         PolicyViolation => (StatusCode::UNPROCESSABLE_ENTITY, err.to_string()),
         InvalidProof => (StatusCode::BAD_REQUEST, err.to_string()),
         CommitmentMismatch => (StatusCode::BAD_REQUEST, err.to_string()),
+        StorageFailure => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
     }
 }
@@ -4,7 +4,8 @@ use std::sync::{Arc, Mutex};
 use tokio::net::TcpListener;
 
 use zkcg_verifier::engine::VerifierEngine;
-use zkcg_common::state::ProtocolState;
+use zkcg_verifier::epoch::{Epoch, EpochSchedule};
+use zkcg_verifier::storage::FileStateStore;
 
 use api::handler::{submit_proof, AppState};
 
@@ -19,16 +20,33 @@ use api::handler::{submit_proof, AppState};
 
 #[tokio::main]
 async fn main() {
+    // params and vk come from the artifact file the prover wrote
     #[cfg(feature = "zk-halo2")]
-    let backend = Box::new(verifier::backend_halo2::Halo2Backend);
+    let backend = Box::new(
+        zkcg_verifier::Halo2Backend::read_from(
+            std::env::var("ZKCG_HALO2_ARTIFACTS").expect("ZKCG_HALO2_ARTIFACTS is not set"),
+        )
+        .expect("failed to load Halo2 artifacts"),
+    );
 
     #[cfg(not(feature = "zk-halo2"))]
     let backend = Box::new(zkcg_verifier::backend_stub::StubBackend::default());
 
-    let engine = VerifierEngine::new(
-        ProtocolState::genesis(),
-        backend,
-    );
+    // state survives restarts; a missing or corrupt file stops startup
+    // instead of falling back to genesis and reopening replay of old
+    // nonces, so genesis is only written when explicitly asked for
+    let state_path = std::env::var("ZKCG_STATE_PATH").expect("ZKCG_STATE_PATH is not set");
+    let store = if std::env::var("ZKCG_INIT_GENESIS").is_ok_and(|v| v == "1") {
+        FileStateStore::init(state_path).expect("failed to initialise verifier state")
+    } else {
+        FileStateStore::new(state_path)
+    };
+
+    // the server runs a single backend; states past epoch 0 need a
+    // schedule registering their epochs, and fail to open here
+    let schedule = EpochSchedule::new().with_epoch(0, Epoch::new(backend, "default"));
+    let engine = VerifierEngine::open(Box::new(store), schedule)
+        .expect("failed to open verifier state");
 
 
     let state = AppState {
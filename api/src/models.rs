@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use zkcg_common::types::Hash;
+use zkcg_common::{state::ProtocolState, types::Hash};
 
 #[derive(Debug, Deserialize)]
 pub struct SubmitProofRequest {
@@ -13,9 +13,18 @@ pub struct PublicInputsDto {
     pub threshold: u64,
     pub old_state_root: Hash,
     pub nonce: u64,
+    pub payload_commitment: Hash,
+    pub dataset_root: Hash,
 }
 
 #[derive(Debug, Serialize)]
 pub struct SubmitProofResponse {
     pub status: String,
+    pub previous_state: ProtocolState,
+    pub new_state: ProtocolState,
+    pub proof_hash: Hash,
+    pub transition_hash: Hash,
+    pub backend: String,
+    pub policy: String,
+    pub verification_micros: u64,
 }
@@ -1,12 +1,51 @@
+use std::{
+    fs,
+    io,
+    path::Path,
+};
+
 use halo2_proofs::{
-    plonk::{keygen_vk, VerifyingKey},
+    plonk::{keygen_vk, Circuit, VerifyingKey},
     poly::commitment::Params,
+    transcript::{Challenge255, EncodedChallenge, Transcript},
+};
+use halo2curves::{
+    bn256::{Fr, G1Affine},
+    ff::PrimeField,
 };
-use halo2curves::bn256::{Fr, G1Affine};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
 
 use crate::score_circuit::ScoreCircuit;
 
+/// Circuit size shared by prover and verifier
+pub const SCORE_CIRCUIT_K: u32 = 10;
+
+/// Artifact file header, bumped on any format change
+const MAGIC: &[u8; 8] = b"ZKCGH2A1";
+
+#[derive(Debug, Error)]
+pub enum Halo2ArtifactsError {
+    #[error("artifact io error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("artifact file is not a ZKCG Halo2 artifact")]
+    BadMagic,
+
+    #[error("artifact fingerprint does not match its contents")]
+    Corrupt,
+
+    #[error("verifying key does not match the compiled circuit")]
+    CircuitMismatch,
+
+    #[error("verifying key generation failed")]
+    Keygen,
+}
+
 /// Canonical verifier artifacts for the ScoreCircuit
+///
+/// `params` are IPA parameters, derived deterministically from `k`;
+/// there is no trusted setup or SRS file involved.
 #[derive(Clone)]
 pub struct Halo2Artifacts {
     pub params: Params<G1Affine>,
@@ -16,35 +55,135 @@ pub struct Halo2Artifacts {
 impl Halo2Artifacts {
     pub fn for_score_circuit() -> Self {
         // MUST match prover + verifier
-        let k: u32 = 9;
+        let params: Params<G1Affine> = Params::new(SCORE_CIRCUIT_K);
 
-        let params: Params<G1Affine> = Params::new(k);
+        Self::for_circuit::<ScoreCircuit<Fr>>(params)
+            .expect("verifying key generation failed")
+    }
 
-        let empty = ScoreCircuit::<Fr> {
-            score: halo2_proofs::circuit::Value::unknown(),
-            threshold: halo2_proofs::circuit::Value::unknown(),
-        };
+    /// Derive the verifying key of circuit `C` under `params`
+    pub fn for_circuit<C: Circuit<Fr> + Default>(
+        params: Params<G1Affine>,
+    ) -> Result<Self, Halo2ArtifactsError> {
+        let vk = keygen_vk(&params, &C::default())
+            .map_err(|_| Halo2ArtifactsError::Keygen)?;
 
-        let vk =
-            keygen_vk(&params, &empty).expect("verifying key generation failed");
+        Ok(Self { params, vk })
+    }
 
-        Self { params, vk }
+    /// Digest identifying the verifying key (constraint system,
+    /// fixed commitments and permutation)
+    ///
+    /// Hashes the serialized scalar the key contributes to every proof
+    /// transcript, so two keys share a digest iff they accept the same
+    /// proofs.
+    pub fn vk_digest(&self) -> [u8; 32] {
+        let mut capture = VkRepr(None);
+        self.vk
+            .hash_into(&mut capture)
+            .expect("capturing the vk representative cannot fail");
+        let repr = capture.0.expect("hash_into writes the vk representative");
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"zkcg/halo2-vk/v1");
+        hasher.update(repr.to_repr());
+        hasher.finalize().into()
+    }
+
+    /// Content fingerprint over params and verifying key
+    ///
+    /// Prover and verifier hold the same artifacts iff fingerprints match.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let mut params_bytes = Vec::new();
+        self.params
+            .write(&mut params_bytes)
+            .expect("writing params to memory cannot fail");
+
+        fingerprint(&self.vk_digest(), &params_bytes)
+    }
+
+    /// Persist params and verifying key digest
+    ///
+    /// Layout: magic || vk digest || params || fingerprint
+    ///
+    /// halo2_proofs 0.3 cannot serialize a verifying key, so only its
+    /// digest is stored; `read_from` re-derives the key from the params
+    /// and checks it against that digest.
+    pub fn write_to(&self, path: impl AsRef<Path>) -> Result<(), Halo2ArtifactsError> {
+        let vk_digest = self.vk_digest();
+
+        let mut params_bytes = Vec::new();
+        self.params.write(&mut params_bytes)?;
+
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 64 + params_bytes.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&vk_digest);
+        bytes.extend_from_slice(&params_bytes);
+        bytes.extend_from_slice(&fingerprint(&vk_digest, &params_bytes));
+
+        fs::write(path, bytes)?;
+
+        Ok(())
+    }
+
+    /// Load artifacts for the ScoreCircuit
+    pub fn read_from(path: impl AsRef<Path>) -> Result<Self, Halo2ArtifactsError> {
+        Self::read_for_circuit::<ScoreCircuit<Fr>>(path)
+    }
+
+    /// Load artifacts and check them against circuit `C`
+    pub fn read_for_circuit<C: Circuit<Fr> + Default>(
+        path: impl AsRef<Path>,
+    ) -> Result<Self, Halo2ArtifactsError> {
+        let bytes = fs::read(path)?;
+
+        if bytes.len() < MAGIC.len() + 64 || !bytes.starts_with(MAGIC) {
+            return Err(Halo2ArtifactsError::BadMagic);
+        }
+
+        let (body, stored_fingerprint) = bytes.split_at(bytes.len() - 32);
+        let (vk_digest, params_bytes) = body[MAGIC.len()..].split_at(32);
+
+        if fingerprint(vk_digest, params_bytes) != stored_fingerprint {
+            return Err(Halo2ArtifactsError::Corrupt);
+        }
+
+        let params = Params::read(&mut &params_bytes[..])?;
+        let artifacts = Self::for_circuit::<C>(params)?;
+
+        if artifacts.vk_digest() != vk_digest {
+            return Err(Halo2ArtifactsError::CircuitMismatch);
+        }
+
+        Ok(artifacts)
     }
 }
 
-/// Generate verifier artifacts at runtime
-pub fn verifier_artifacts() -> Halo2Artifacts {
-    let k: u32 = 9;
+/// Transcript that only records the verifying key's common scalar
+///
+/// halo2_proofs 0.3 has no verifying key serialization; its transcript
+/// representative is the canonical encoding proofs are bound to.
+struct VkRepr(Option<Fr>);
 
-    let params: Params<G1Affine> = Params::new(k);
+impl Transcript<G1Affine, Challenge255<G1Affine>> for VkRepr {
+    fn squeeze_challenge(&mut self) -> Challenge255<G1Affine> {
+        Challenge255::new(&[0u8; 64])
+    }
 
-    let empty_circuit = ScoreCircuit::<Fr> {
-        score: halo2_proofs::circuit::Value::unknown(),
-        threshold: halo2_proofs::circuit::Value::unknown(),
-    };
+    fn common_point(&mut self, _point: G1Affine) -> io::Result<()> {
+        Ok(())
+    }
 
-    let vk = keygen_vk(&params, &empty_circuit)
-        .expect("failed to generate verifying key");
+    fn common_scalar(&mut self, scalar: Fr) -> io::Result<()> {
+        self.0 = Some(scalar);
+        Ok(())
+    }
+}
 
-    Halo2Artifacts { params, vk }
+fn fingerprint(vk_digest: &[u8], params_bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(MAGIC);
+    hasher.update(vk_digest);
+    hasher.update(params_bytes);
+    hasher.finalize().into()
 }
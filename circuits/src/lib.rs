@@ -1,5 +1,12 @@
+pub mod merkle;
+pub mod poseidon;
+pub mod range_check;
 pub mod score_circuit;
+pub mod weighted_score_circuit;
 pub mod halo2_artifacts;
 
 #[cfg(test)]
 mod tests;
+
+#[cfg(test)]
+mod tests_weighted;
@@ -0,0 +1,176 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use halo2curves::ff::PrimeField;
+
+use crate::poseidon::{self, PoseidonConfig};
+
+/// Parent node of a Merkle tree: H(left, right)
+pub fn merkle_node<F: PrimeField>(left: F, right: F) -> F {
+    poseidon::hash(&[left, right])
+}
+
+/// Root reached from `leaf` along `siblings`, leaf level first
+///
+/// Bit i of `index` is set when the running node is the right child
+/// at level i.
+pub fn merkle_root<F: PrimeField>(leaf: F, siblings: &[F], index: u64) -> F {
+    siblings
+        .iter()
+        .enumerate()
+        .fold(leaf, |node, (level, sibling)| {
+            if (index >> level) & 1 == 1 {
+                merkle_node(*sibling, node)
+            } else {
+                merkle_node(node, *sibling)
+            }
+        })
+}
+
+/// Root of the tree over `leaves` and the sibling path of `leaves[index]`
+///
+/// `leaves.len()` must be a power of two.
+pub fn merkle_path<F: PrimeField>(leaves: &[F], index: usize) -> (F, Vec<F>) {
+    assert!(
+        leaves.len().is_power_of_two() && index < leaves.len(),
+        "leaves must be a power of two and index in range"
+    );
+
+    let mut level = leaves.to_vec();
+    let mut position = index;
+    let mut siblings = Vec::new();
+
+    while level.len() > 1 {
+        siblings.push(level[position ^ 1]);
+        level = level
+            .chunks(2)
+            .map(|pair| merkle_node(pair[0], pair[1]))
+            .collect();
+        position /= 2;
+    }
+
+    (level[0], siblings)
+}
+
+/// Merkle path verification
+///
+/// Per level, one swap row orders the running node and its sibling by
+/// the private position bit:
+///
+///   bit ∈ {0,1}
+///   left  = node + bit * (sibling - node)
+///   right = sibling + bit * (node - sibling)
+///
+/// and the parent H(left, right) is computed with the Poseidon chip.
+/// Neither the leaf nor its position is revealed; only the root is
+/// meant to be constrained to an instance.
+#[derive(Clone, Debug)]
+pub struct MerkleConfig {
+    node: Column<Advice>,
+    sibling: Column<Advice>,
+    bit: Column<Advice>,
+    left: Column<Advice>,
+    right: Column<Advice>,
+    q_swap: Selector,
+}
+
+impl MerkleConfig {
+    pub fn configure<F: PrimeField>(cs: &mut ConstraintSystem<F>) -> Self {
+        let node = cs.advice_column();
+        let sibling = cs.advice_column();
+        let bit = cs.advice_column();
+        let left = cs.advice_column();
+        let right = cs.advice_column();
+        let q_swap = cs.selector();
+
+        cs.enable_equality(node);
+        cs.enable_equality(left);
+        cs.enable_equality(right);
+
+        cs.create_gate("merkle swap", |meta| {
+            let s = meta.query_selector(q_swap);
+            let node = meta.query_advice(node, Rotation::cur());
+            let sibling = meta.query_advice(sibling, Rotation::cur());
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let left = meta.query_advice(left, Rotation::cur());
+            let right = meta.query_advice(right, Rotation::cur());
+
+            vec![
+                s.clone() * bit.clone() * (Expression::Constant(F::ONE) - bit.clone()),
+                s.clone()
+                    * (left - node.clone() - bit.clone() * (sibling.clone() - node.clone())),
+                s * (right - sibling.clone() - bit * (node - sibling)),
+            ]
+        });
+
+        Self {
+            node,
+            sibling,
+            bit,
+            left,
+            right,
+            q_swap,
+        }
+    }
+
+    /// Assign the path from `leaf` and return the root cell
+    ///
+    /// `index` bits select the side at each level, as in `merkle_root`.
+    pub fn root<F: PrimeField>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        poseidon: &PoseidonConfig,
+        leaf: &AssignedCell<F, F>,
+        siblings: &[Value<F>],
+        index: Value<u64>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let mut node = leaf.clone();
+
+        for (level, sibling) in siblings.iter().enumerate() {
+            let bit = index.map(|index| (index >> level) & 1 == 1);
+
+            let (left, right) = layouter.assign_region(
+                || format!("merkle level {}", level),
+                |mut region| {
+                    self.q_swap.enable(&mut region, 0)?;
+
+                    node.copy_advice(|| "node", &mut region, self.node, 0)?;
+                    region.assign_advice(|| "sibling", self.sibling, 0, || *sibling)?;
+                    region.assign_advice(
+                        || "position bit",
+                        self.bit,
+                        0,
+                        || bit.map(|b| F::from(b as u64)),
+                    )?;
+
+                    let pair = node.value().copied().zip(*sibling).zip(bit).map(
+                        |((node, sibling), bit)| {
+                            if bit {
+                                (sibling, node)
+                            } else {
+                                (node, sibling)
+                            }
+                        },
+                    );
+
+                    let left =
+                        region.assign_advice(|| "left", self.left, 0, || pair.map(|p| p.0))?;
+                    let right =
+                        region.assign_advice(|| "right", self.right, 0, || pair.map(|p| p.1))?;
+
+                    Ok((left, right))
+                },
+            )?;
+
+            node = poseidon.hash(
+                layouter.namespace(|| format!("merkle node {}", level)),
+                &[left, right],
+            )?;
+        }
+
+        Ok(node)
+    }
+}
@@ -0,0 +1,411 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+
+use halo2curves::ff::PrimeField;
+
+/// Permutation width (rate + capacity)
+pub const WIDTH: usize = 3;
+
+/// Field elements absorbed per permutation
+pub const RATE: usize = 2;
+
+pub const FULL_ROUNDS: usize = 8;
+pub const PARTIAL_ROUNDS: usize = 57;
+
+const ROUNDS: usize = FULL_ROUNDS + PARTIAL_ROUNDS;
+
+/// Round constants and MDS matrix for Poseidon with x^5 S-boxes
+///
+/// Round constants come from the Grain LFSR of the Poseidon paper,
+/// seeded with the field size and round numbers. The MDS matrix is the
+/// Cauchy matrix M[i][j] = 1 / (i + WIDTH + j).
+#[derive(Clone, Debug)]
+pub struct PoseidonParams<F: PrimeField> {
+    pub round_constants: Vec<[F; WIDTH]>,
+    pub mds: [[F; WIDTH]; WIDTH],
+}
+
+impl<F: PrimeField> Default for PoseidonParams<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: PrimeField> PoseidonParams<F> {
+    pub fn new() -> Self {
+        let mut grain = Grain::new(F::NUM_BITS);
+
+        let round_constants = (0..ROUNDS)
+            .map(|_| [(); WIDTH].map(|_| grain.next_field_element::<F>()))
+            .collect();
+
+        let mds = std::array::from_fn(|i| {
+            std::array::from_fn(|j| {
+                F::from((i + WIDTH + j) as u64)
+                    .invert()
+                    .expect("Cauchy denominators are non-zero")
+            })
+        });
+
+        Self {
+            round_constants,
+            mds,
+        }
+    }
+
+    fn is_full_round(round: usize) -> bool {
+        !(FULL_ROUNDS / 2..FULL_ROUNDS / 2 + PARTIAL_ROUNDS).contains(&round)
+    }
+
+    fn mix(&self, state: &[F; WIDTH]) -> [F; WIDTH] {
+        std::array::from_fn(|i| {
+            self.mds[i]
+                .iter()
+                .zip(state.iter())
+                .fold(F::ZERO, |acc, (m, s)| acc + *m * s)
+        })
+    }
+
+    /// Apply the Poseidon permutation in place
+    pub fn permute(&self, state: &mut [F; WIDTH]) {
+        for (round, constants) in self.round_constants.iter().enumerate() {
+            for (s, c) in state.iter_mut().zip(constants.iter()) {
+                *s += c;
+            }
+
+            if Self::is_full_round(round) {
+                for s in state.iter_mut() {
+                    *s = pow5(*s);
+                }
+            } else {
+                state[0] = pow5(state[0]);
+            }
+
+            *state = self.mix(state);
+        }
+    }
+}
+
+fn pow5<F: PrimeField>(x: F) -> F {
+    x.square().square() * x
+}
+
+/// Sponge capacity element for a fixed-length input
+fn domain<F: PrimeField>(len: usize) -> F {
+    F::from_u128((len as u128) << 64)
+}
+
+/// Native Poseidon hash of a non-empty, fixed-length input
+///
+/// Matches `PoseidonConfig::hash` in-circuit.
+pub fn hash<F: PrimeField>(inputs: &[F]) -> F {
+    assert!(!inputs.is_empty(), "poseidon input must not be empty");
+
+    let params = PoseidonParams::<F>::new();
+
+    let mut state = [F::ZERO; WIDTH];
+    state[RATE] = domain(inputs.len());
+
+    for chunk in inputs.chunks(RATE) {
+        for (s, x) in state.iter_mut().zip(chunk) {
+            *s += x;
+        }
+        params.permute(&mut state);
+    }
+
+    state[0]
+}
+
+/// Grain LFSR used to derive round constants
+struct Grain {
+    state: [bool; 80],
+}
+
+impl Grain {
+    fn new(field_bits: u32) -> Self {
+        let mut bits = Vec::with_capacity(80);
+        let mut push = |value: usize, len: usize| {
+            bits.extend((0..len).rev().map(|i| (value >> i) & 1 == 1));
+        };
+
+        push(1, 2); // prime field
+        push(0, 4); // x^alpha S-box
+        push(field_bits as usize, 12);
+        push(WIDTH, 12);
+        push(FULL_ROUNDS, 10);
+        push(PARTIAL_ROUNDS, 10);
+        push((1 << 30) - 1, 30);
+
+        let mut grain = Self {
+            state: bits.try_into().expect("grain state is 80 bits"),
+        };
+
+        // discard the first 160 output bits
+        for _ in 0..160 {
+            grain.clock();
+        }
+
+        grain
+    }
+
+    fn clock(&mut self) -> bool {
+        let s = &self.state;
+        let bit = s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0];
+
+        self.state.rotate_left(1);
+        self.state[79] = bit;
+
+        bit
+    }
+
+    /// Self-shrinking output: a 1 keeps the following bit, a 0 drops it
+    fn next_bit(&mut self) -> bool {
+        loop {
+            let keep = self.clock();
+            let bit = self.clock();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    /// Sample big-endian bit strings until one is a canonical element
+    fn next_field_element<F: PrimeField>(&mut self) -> F {
+        loop {
+            let mut repr = F::Repr::default();
+            let bytes = repr.as_mut();
+
+            for i in (0..F::NUM_BITS as usize).rev() {
+                if self.next_bit() {
+                    bytes[i / 8] |= 1 << (i % 8);
+                }
+            }
+
+            if let Some(value) = Option::from(F::from_repr(repr)) {
+                return value;
+            }
+        }
+    }
+}
+
+/// In-circuit Poseidon sponge, one row per round
+///
+/// Per absorbed chunk:
+///   row r:     state_in, input chunk       (q_absorb)
+///   row r + 1: state_in + input chunk      (first round)
+///   ...        one row per round           (q_full / q_partial)
+///   row r + 1 + ROUNDS: permutation output
+#[derive(Clone, Debug)]
+pub struct PoseidonConfig {
+    state: [Column<Advice>; WIDTH],
+    input: [Column<Advice>; RATE],
+    round_constants: [Column<Fixed>; WIDTH],
+    q_absorb: Selector,
+    q_full: Selector,
+    q_partial: Selector,
+}
+
+impl PoseidonConfig {
+    pub fn configure<F: PrimeField>(cs: &mut ConstraintSystem<F>) -> Self {
+        let state = [(); WIDTH].map(|_| cs.advice_column());
+        let input = [(); RATE].map(|_| cs.advice_column());
+        let round_constants = [(); WIDTH].map(|_| cs.fixed_column());
+        let constants = cs.fixed_column();
+        let q_absorb = cs.selector();
+        let q_full = cs.selector();
+        let q_partial = cs.selector();
+
+        cs.enable_constant(constants);
+        for column in state.iter().chain(input.iter()) {
+            cs.enable_equality(*column);
+        }
+
+        let mds = PoseidonParams::<F>::new().mds;
+
+        let pow5 = |x: Expression<F>| {
+            let x2 = x.clone() * x.clone();
+            x2.clone() * x2 * x
+        };
+
+        // next = MDS * sbox(cur + rc), S-box on every element or only the first
+        let mut round_gate = |name: &'static str, selector: Selector, full: bool| {
+            cs.create_gate(name, |meta| {
+                let s = meta.query_selector(selector);
+
+                let sboxed: Vec<Expression<F>> = (0..WIDTH)
+                    .map(|j| {
+                        let x = meta.query_advice(state[j], Rotation::cur())
+                            + meta.query_fixed(round_constants[j]);
+                        if full || j == 0 {
+                            pow5(x)
+                        } else {
+                            x
+                        }
+                    })
+                    .collect();
+
+                (0..WIDTH)
+                    .map(|i| {
+                        let next = meta.query_advice(state[i], Rotation::next());
+                        let mixed = sboxed.iter().enumerate().fold(
+                            Expression::Constant(F::ZERO),
+                            |acc, (j, x)| acc + x.clone() * Expression::Constant(mds[i][j]),
+                        );
+                        s.clone() * (next - mixed)
+                    })
+                    .collect::<Vec<_>>()
+            });
+        };
+
+        round_gate("poseidon full round", q_full, true);
+        round_gate("poseidon partial round", q_partial, false);
+
+        // next = cur + input (capacity untouched)
+        cs.create_gate("poseidon absorb", |meta| {
+            let s = meta.query_selector(q_absorb);
+
+            (0..WIDTH)
+                .map(|i| {
+                    let cur = meta.query_advice(state[i], Rotation::cur());
+                    let next = meta.query_advice(state[i], Rotation::next());
+                    let added = if i < RATE {
+                        meta.query_advice(input[i], Rotation::cur())
+                    } else {
+                        Expression::Constant(F::ZERO)
+                    };
+                    s.clone() * (next - cur - added)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        Self {
+            state,
+            input,
+            round_constants,
+            q_absorb,
+            q_full,
+            q_partial,
+        }
+    }
+
+    /// Hash a non-empty, fixed-length list of cells
+    pub fn hash<F: PrimeField>(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(!inputs.is_empty(), "poseidon input must not be empty");
+
+        let params = PoseidonParams::<F>::new();
+
+        layouter.assign_region(
+            || "poseidon hash",
+            |mut region| {
+                let initial = [F::ZERO, F::ZERO, domain(inputs.len())];
+                let mut cells = initial
+                    .iter()
+                    .enumerate()
+                    .map(|(i, value)| {
+                        region.assign_advice_from_constant(
+                            || format!("initial state {}", i),
+                            self.state[i],
+                            0,
+                            *value,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let mut state = Value::known(initial);
+                let mut row = 0;
+
+                for chunk in inputs.chunks(RATE) {
+                    self.q_absorb.enable(&mut region, row)?;
+
+                    for i in 0..RATE {
+                        match chunk.get(i) {
+                            Some(cell) => {
+                                cell.copy_advice(
+                                    || format!("input {}", i),
+                                    &mut region,
+                                    self.input[i],
+                                    row,
+                                )?;
+                                state = state.zip(cell.value().copied()).map(|(mut s, x)| {
+                                    s[i] += x;
+                                    s
+                                });
+                            }
+                            None => {
+                                region.assign_advice_from_constant(
+                                    || format!("padding {}", i),
+                                    self.input[i],
+                                    row,
+                                    F::ZERO,
+                                )?;
+                            }
+                        }
+                    }
+
+                    row += 1;
+                    cells = self.assign_state(&mut region, row, state)?;
+
+                    for (round, constants) in params.round_constants.iter().enumerate() {
+                        let full = PoseidonParams::<F>::is_full_round(round);
+                        if full {
+                            self.q_full.enable(&mut region, row)?;
+                        } else {
+                            self.q_partial.enable(&mut region, row)?;
+                        }
+
+                        for (j, c) in constants.iter().enumerate() {
+                            region.assign_fixed(
+                                || format!("round constant {}", j),
+                                self.round_constants[j],
+                                row,
+                                || Value::known(*c),
+                            )?;
+                        }
+
+                        state = state.map(|mut s| {
+                            for (x, c) in s.iter_mut().zip(constants.iter()) {
+                                *x += c;
+                            }
+                            if full {
+                                s = s.map(pow5);
+                            } else {
+                                s[0] = pow5(s[0]);
+                            }
+                            params.mix(&s)
+                        });
+
+                        row += 1;
+                        cells = self.assign_state(&mut region, row, state)?;
+                    }
+                }
+
+                Ok(cells[0].clone())
+            },
+        )
+    }
+
+    fn assign_state<F: PrimeField>(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        state: Value<[F; WIDTH]>,
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        (0..WIDTH)
+            .map(|i| {
+                region.assign_advice(
+                    || format!("state {}", i),
+                    self.state[i],
+                    row,
+                    || state.map(|s| s[i]),
+                )
+            })
+            .collect()
+    }
+}
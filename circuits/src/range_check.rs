@@ -0,0 +1,352 @@
+use std::fmt::Debug;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{
+        Advice, Column, ConstraintSystem, Error, Expression, Selector, TableColumn,
+        VirtualCells,
+    },
+    poly::Rotation,
+};
+
+use halo2curves::ff::PrimeField;
+
+/// Bits decomposed per row by `BitRangeConfig`
+pub const CHUNK_BITS: usize = 16;
+
+/// Limb width looked up by `LookupRangeConfig`
+pub const LIMB_BITS: usize = 8;
+
+/// A range-check layout usable by ScoreCircuit
+pub trait RangeCheck<F: PrimeField>: Clone + Debug {
+    /// Checked widths must be a multiple of this
+    const CHUNK_BITS: usize;
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self;
+
+    /// Assign any fixed tables; called once per synthesis
+    fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error>;
+
+    /// Constrain `cell` to [0, 2^num_bits)
+    fn assign(
+        &self,
+        layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<(), Error>;
+}
+
+/// Little-endian bits of a field element, read from its full canonical repr
+pub fn le_bits<F: PrimeField>(value: &F, num_bits: usize) -> Vec<bool> {
+    let repr = value.to_repr();
+    let bytes = repr.as_ref();
+
+    (0..num_bits)
+        .map(|i| {
+            bytes
+                .get(i / 8)
+                .map(|b| (b >> (i % 8)) & 1 == 1)
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+fn chunk_rows(num_bits: usize, chunk_bits: usize) -> usize {
+    assert!(
+        num_bits > 0 && num_bits.is_multiple_of(chunk_bits),
+        "range width must be a multiple of {}",
+        chunk_bits
+    );
+
+    num_bits / chunk_bits
+}
+
+/// Range check by bit decomposition
+///
+/// A value is split into `CHUNK_BITS`-wide chunks, most significant
+/// first, one chunk per row. Each row holds the chunk's bits and a
+/// running sum:
+///
+///   first row: acc = chunk
+///   next rows: acc = acc_prev * 2^CHUNK_BITS + chunk
+///
+/// The final running sum is copy-constrained to the checked cell,
+/// so the cell is proven to lie in [0, 2^num_bits).
+#[derive(Clone, Debug)]
+pub struct BitRangeConfig {
+    bits: [Column<Advice>; CHUNK_BITS],
+    acc: Column<Advice>,
+    q_first: Selector,
+    q_next: Selector,
+}
+
+impl<F: PrimeField> RangeCheck<F> for BitRangeConfig {
+    const CHUNK_BITS: usize = CHUNK_BITS;
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self {
+        let bits = [(); CHUNK_BITS].map(|_| cs.advice_column());
+        let acc = cs.advice_column();
+        let q_first = cs.selector();
+        let q_next = cs.selector();
+
+        cs.enable_equality(acc);
+
+        let chunk = |meta: &mut VirtualCells<'_, F>| {
+            bits.iter().enumerate().fold(
+                Expression::Constant(F::ZERO),
+                |acc, (i, bit)| {
+                    acc + meta.query_advice(*bit, Rotation::cur())
+                        * Expression::Constant(F::from_u128(1u128 << i))
+                },
+            )
+        };
+
+        // bit ∈ {0,1}
+        let booleans = |meta: &mut VirtualCells<'_, F>| {
+            bits.iter()
+                .map(|bit| {
+                    let b = meta.query_advice(*bit, Rotation::cur());
+                    b.clone() * (Expression::Constant(F::ONE) - b)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // acc == sum(bits * 2^i)
+        cs.create_gate("first chunk", |meta| {
+            let s = meta.query_selector(q_first);
+            let acc = meta.query_advice(acc, Rotation::cur());
+
+            let mut constraints = booleans(meta);
+            constraints.push(acc - chunk(meta));
+
+            constraints.into_iter().map(|c| s.clone() * c).collect::<Vec<_>>()
+        });
+
+        // acc == acc_prev * 2^CHUNK_BITS + sum(bits * 2^i)
+        cs.create_gate("next chunk", |meta| {
+            let s = meta.query_selector(q_next);
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let shift = Expression::Constant(F::from_u128(1u128 << CHUNK_BITS));
+
+            let mut constraints = booleans(meta);
+            constraints.push(acc_cur - acc_prev * shift - chunk(meta));
+
+            constraints.into_iter().map(|c| s.clone() * c).collect::<Vec<_>>()
+        });
+
+        Self {
+            bits,
+            acc,
+            q_first,
+            q_next,
+        }
+    }
+
+    fn load(&self, _layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// `num_bits` must be a non-zero multiple of `CHUNK_BITS`.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<(), Error> {
+        let rows = chunk_rows(num_bits, CHUNK_BITS);
+
+        layouter.assign_region(
+            || format!("range check {} bits", num_bits),
+            |mut region| {
+                let bits = cell.value().map(|v| le_bits(v, num_bits));
+                let mut acc = Value::known(F::ZERO);
+
+                for row in 0..rows {
+                    if row == 0 {
+                        self.q_first.enable(&mut region, row)?;
+                    } else {
+                        self.q_next.enable(&mut region, row)?;
+                    }
+
+                    // most significant chunk first
+                    let offset = (rows - 1 - row) * CHUNK_BITS;
+                    let mut chunk = Value::known(F::ZERO);
+
+                    for (i, column) in self.bits.iter().enumerate() {
+                        let bit = bits
+                            .as_ref()
+                            .map(|bits| F::from(bits[offset + i] as u64));
+
+                        region.assign_advice(
+                            || format!("bit {}", offset + i),
+                            *column,
+                            row,
+                            || bit,
+                        )?;
+
+                        chunk = chunk
+                            + bit.map(|b| b * F::from_u128(1u128 << i));
+                    }
+
+                    acc = acc.map(|a| a * F::from_u128(1u128 << CHUNK_BITS)) + chunk;
+
+                    if row + 1 == rows {
+                        // bind the running sum to the checked value
+                        cell.copy_advice(|| "range checked", &mut region, self.acc, row)?;
+                    } else {
+                        region.assign_advice(|| "running sum", self.acc, row, || acc)?;
+                    }
+                }
+
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Range check by lookup
+///
+/// A value is split into `LIMB_BITS`-wide limbs, most significant
+/// first, one limb per row, with the same running sum as
+/// `BitRangeConfig`:
+///
+///   first row: acc = limb
+///   next rows: acc = acc_prev * 2^LIMB_BITS + limb
+///
+/// Instead of bit columns and boolean gates, every limb is looked up
+/// in a fixed table of [0, 2^LIMB_BITS). Widening the range adds rows,
+/// never columns. The table needs 2^LIMB_BITS usable rows.
+#[derive(Clone, Debug)]
+pub struct LookupRangeConfig {
+    limb: Column<Advice>,
+    acc: Column<Advice>,
+    table: TableColumn,
+    q_lookup: Selector,
+    q_first: Selector,
+    q_next: Selector,
+}
+
+impl<F: PrimeField> RangeCheck<F> for LookupRangeConfig {
+    const CHUNK_BITS: usize = LIMB_BITS;
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self {
+        let limb = cs.advice_column();
+        let acc = cs.advice_column();
+        let table = cs.lookup_table_column();
+        let q_lookup = cs.complex_selector();
+        let q_first = cs.selector();
+        let q_next = cs.selector();
+
+        cs.enable_equality(acc);
+
+        // limb ∈ [0, 2^LIMB_BITS)
+        cs.lookup(|meta| {
+            let q = meta.query_selector(q_lookup);
+            let limb = meta.query_advice(limb, Rotation::cur());
+
+            vec![(q * limb, table)]
+        });
+
+        // acc == limb
+        cs.create_gate("first limb", |meta| {
+            let s = meta.query_selector(q_first);
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let limb = meta.query_advice(limb, Rotation::cur());
+
+            vec![s * (acc - limb)]
+        });
+
+        // acc == acc_prev * 2^LIMB_BITS + limb
+        cs.create_gate("next limb", |meta| {
+            let s = meta.query_selector(q_next);
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let limb = meta.query_advice(limb, Rotation::cur());
+            let shift = Expression::Constant(F::from_u128(1u128 << LIMB_BITS));
+
+            vec![s * (acc_cur - acc_prev * shift - limb)]
+        });
+
+        Self {
+            limb,
+            acc,
+            table,
+            q_lookup,
+            q_first,
+            q_next,
+        }
+    }
+
+    fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "limb table",
+            |mut table| {
+                for value in 0..(1u64 << LIMB_BITS) {
+                    table.assign_cell(
+                        || "limb value",
+                        self.table,
+                        value as usize,
+                        || Value::known(F::from(value)),
+                    )?;
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    /// `num_bits` must be a non-zero multiple of `LIMB_BITS`.
+    fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<(), Error> {
+        let rows = chunk_rows(num_bits, LIMB_BITS);
+
+        layouter.assign_region(
+            || format!("lookup range check {} bits", num_bits),
+            |mut region| {
+                let bits = cell.value().map(|v| le_bits(v, num_bits));
+                let mut acc = Value::known(F::ZERO);
+
+                for row in 0..rows {
+                    self.q_lookup.enable(&mut region, row)?;
+
+                    if row == 0 {
+                        self.q_first.enable(&mut region, row)?;
+                    } else {
+                        self.q_next.enable(&mut region, row)?;
+                    }
+
+                    // most significant limb first
+                    let offset = (rows - 1 - row) * LIMB_BITS;
+                    let limb = bits.as_ref().map(|bits| {
+                        let limb = (0..LIMB_BITS)
+                            .fold(0u64, |acc, i| acc | ((bits[offset + i] as u64) << i));
+                        F::from(limb)
+                    });
+
+                    region.assign_advice(
+                        || format!("limb {}", row),
+                        self.limb,
+                        row,
+                        || limb,
+                    )?;
+
+                    acc = acc.map(|a| a * F::from_u128(1u128 << LIMB_BITS)) + limb;
+
+                    if row + 1 == rows {
+                        // bind the running sum to the checked value
+                        cell.copy_advice(|| "range checked", &mut region, self.acc, row)?;
+                    } else {
+                        region.assign_advice(|| "running sum", self.acc, row, || acc)?;
+                    }
+                }
+
+                Ok(())
+            },
+        )
+    }
+}
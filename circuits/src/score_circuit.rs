@@ -1,107 +1,358 @@
+use std::marker::PhantomData;
+
 use halo2_proofs::{
-    circuit::{Layouter, SimpleFloorPlanner, Value},
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
     plonk::{
-        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector,
+        Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector,
     },
     poly::Rotation,
 };
 
 use halo2curves::ff::PrimeField;
 
-const DIFF_BITS: usize = 16;
+use crate::merkle::{merkle_root, MerkleConfig};
+use crate::poseidon::{self, PoseidonConfig};
+use crate::range_check::{BitRangeConfig, LookupRangeConfig, RangeCheck};
+
+/// Range width of `score` and `threshold`
+pub const VALUE_BITS: usize = 64;
+
+/// Number of 128-bit field limbs carrying the 32-byte state root
+pub const STATE_ROOT_LIMBS: usize = 2;
+
+/// Instance column layout
+pub const THRESHOLD_ROW: usize = 0;
+pub const STATE_ROOT_ROW: usize = 1;
+pub const NONCE_ROW: usize = STATE_ROOT_ROW + STATE_ROOT_LIMBS;
+pub const COMMITMENT_ROW: usize = NONCE_ROW + 1;
+pub const NEW_STATE_ROOT_ROW: usize = COMMITMENT_ROW + 1;
+
+/// ScoreCircuit only: root of the dataset the scored record belongs to
+pub const DATASET_ROOT_ROW: usize = NEW_STATE_ROOT_ROW + 1;
+
+/// Depth of the dataset Merkle tree (up to 2^8 records)
+pub const DATASET_DEPTH: usize = 8;
+
+/// Split a state root into little-endian 128-bit limbs
+pub fn state_root_limbs<F: PrimeField>(root: &[u8; 32]) -> [F; STATE_ROOT_LIMBS] {
+    let mut limbs = [F::ZERO; STATE_ROOT_LIMBS];
+
+    for (limb, chunk) in limbs.iter_mut().zip(root.chunks(16)) {
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(chunk);
+        *limb = F::from_u128(u128::from_le_bytes(bytes));
+    }
+
+    limbs
+}
+
+/// Poseidon commitment to the private score: H(score, blinding)
+pub fn score_commitment<F: PrimeField>(score: u64, blinding: F) -> F {
+    poseidon::hash(&[F::from(score), blinding])
+}
+
+/// Next state root: H(old_state_root limbs, nonce, commitment)
+///
+/// Matches the transition `bind_state` constrains in-circuit.
+pub fn transition_root<F: PrimeField>(old_state_root: &[u8; 32], nonce: u64, commitment: F) -> F {
+    let [lo, hi] = state_root_limbs::<F>(old_state_root);
+    poseidon::hash(&[lo, hi, F::from(nonce), commitment])
+}
+
+/// Dataset leaf of a scored record: H(record id, score, salt)
+///
+/// The id ties the leaf to one record, so records sharing a score have
+/// distinct leaves; the secret salt keeps the score from being recovered
+/// by hashing every candidate value.
+pub fn record_leaf<F: PrimeField>(id: F, score: u64, salt: F) -> F {
+    poseidon::hash(&[id, F::from(score), salt])
+}
+
+/// A record's place in the dataset: its identity, salt and Merkle path
+/// (see `merkle::merkle_path`)
+#[derive(Clone, Copy, Debug)]
+pub struct DatasetRecord<F> {
+    pub id: F,
+    pub salt: F,
+    pub siblings: [F; DATASET_DEPTH],
+    pub index: u64,
+}
+
+impl<F: PrimeField> DatasetRecord<F> {
+    /// Leaf of this record carrying `score`
+    pub fn leaf(&self, score: u64) -> F {
+        record_leaf(self.id, score, self.salt)
+    }
+
+    /// Dataset root the path leads to from this record carrying `score`
+    pub fn root(&self, score: u64) -> F {
+        merkle_root(self.leaf(score), &self.siblings, self.index)
+    }
+}
+
+/// Canonical little-endian encoding of a commitment
+pub fn commitment_to_bytes<F: PrimeField>(commitment: &F) -> [u8; 32] {
+    let repr = commitment.to_repr();
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(repr.as_ref());
+    bytes
+}
+
+/// Decode a commitment; `None` if the bytes are not a canonical field element
+pub fn commitment_from_bytes<F: PrimeField>(bytes: &[u8; 32]) -> Option<F> {
+    let mut repr = F::Repr::default();
+    if repr.as_ref().len() != bytes.len() {
+        return None;
+    }
+    repr.as_mut().copy_from_slice(bytes);
+
+    F::from_repr(repr).into()
+}
+
+/// Public instance column for ScoreCircuit, in layout order
+pub fn public_instances<F: PrimeField>(
+    threshold: u64,
+    old_state_root: &[u8; 32],
+    nonce: u64,
+    commitment: F,
+    dataset_root: F,
+) -> Vec<F> {
+    let mut instances = state_instances(threshold, old_state_root, nonce, commitment);
+    instances.push(dataset_root);
+    instances
+}
+
+/// Instance rows shared by the score circuits, up to `NEW_STATE_ROOT_ROW`
+pub(crate) fn state_instances<F: PrimeField>(
+    threshold: u64,
+    old_state_root: &[u8; 32],
+    nonce: u64,
+    commitment: F,
+) -> Vec<F> {
+    let mut instances = vec![F::from(threshold)];
+    instances.extend(state_root_limbs::<F>(old_state_root));
+    instances.push(F::from(nonce));
+    instances.push(commitment);
+    instances.push(transition_root(old_state_root, nonce, commitment));
+    instances
+}
+
+/// Assign `old_state_root` limbs and `nonce` into `advice`, constrain
+/// them to their rows of the instance column, and expose the next state
+/// root H(old_state_root limbs, nonce, commitment) at `NEW_STATE_ROOT_ROW`
+pub(crate) fn bind_state<F: PrimeField>(
+    layouter: &mut impl Layouter<F>,
+    advice: Column<Advice>,
+    instance: Column<Instance>,
+    poseidon: &PoseidonConfig,
+    old_state_root: &[Value<F>; STATE_ROOT_LIMBS],
+    nonce: Value<F>,
+    commitment: AssignedCell<F, F>,
+) -> Result<(), Error> {
+    let (root_cells, nonce_cell) = layouter.assign_region(
+        || "state binding",
+        |mut region| {
+            let mut root_cells = Vec::with_capacity(STATE_ROOT_LIMBS);
+            for (i, limb) in old_state_root.iter().enumerate() {
+                root_cells.push(region.assign_advice(
+                    || format!("old_state_root limb {}", i),
+                    advice,
+                    i,
+                    || *limb,
+                )?);
+            }
+
+            let nonce_cell = region.assign_advice(
+                || "nonce",
+                advice,
+                STATE_ROOT_LIMBS,
+                || nonce,
+            )?;
+
+            Ok((root_cells, nonce_cell))
+        },
+    )?;
+
+    for (i, cell) in root_cells.iter().enumerate() {
+        layouter.constrain_instance(cell.cell(), instance, STATE_ROOT_ROW + i)?;
+    }
+
+    layouter.constrain_instance(nonce_cell.cell(), instance, NONCE_ROW)?;
+
+    let mut transition = root_cells;
+    transition.push(nonce_cell);
+    transition.push(commitment);
+
+    let new_state_root =
+        poseidon.hash(layouter.namespace(|| "state transition"), &transition)?;
+
+    layouter.constrain_instance(new_state_root.cell(), instance, NEW_STATE_ROOT_ROW)
+}
 
 /// Enforces: score <= threshold
 ///
 /// Constraint model:
 ///   threshold = score + diff
-///   diff >= 0
-///   diff decomposed into bits
+///   score, threshold ∈ [0, 2^64)
+///   diff ∈ [0, 2^DIFF_BITS)
+///
+/// `DIFF_BITS` must be a multiple of the range check's chunk width and
+/// at most 64. With the default of 64 every `score <= threshold` over
+/// u64 is provable.
+///
+/// `R` selects the range-check layout: bit decomposition by default,
+/// or `LookupRangeConfig` (see `LookupScoreCircuit`).
+///
+/// The proof is additionally bound to `old_state_root` and `nonce`,
+/// so it cannot be replayed against another state, and computes the
+/// next state root (see `transition_root`) from them and the commitment.
+///
+/// The private score is committed as H(score, blinding) with Poseidon
+/// and exposed at `COMMITMENT_ROW`, so the caller learns a binding
+/// digest of what was proven without learning the score.
+///
+/// The scored record, as leaf `record_leaf(id, score, salt)`, is proven
+/// to belong to a depth-`DATASET_DEPTH` Merkle tree whose root is
+/// exposed at `DATASET_ROOT_ROW`. Id, salt and path stay private.
 #[derive(Clone)]
-pub struct ScoreCircuit<F: PrimeField> {
+pub struct ScoreCircuit<
+    F: PrimeField,
+    const DIFF_BITS: usize = VALUE_BITS,
+    R: RangeCheck<F> = BitRangeConfig,
+> {
     pub score: Value<F>,
     pub threshold: Value<F>,
+    pub old_state_root: [Value<F>; STATE_ROOT_LIMBS],
+    pub nonce: Value<F>,
+    pub blinding: Value<F>,
+    pub record_id: Value<F>,
+    pub record_salt: Value<F>,
+    pub dataset_siblings: [Value<F>; DATASET_DEPTH],
+    pub dataset_index: Value<u64>,
+    pub(crate) range: PhantomData<R>,
+}
+
+/// ScoreCircuit with lookup-table range checks
+pub type LookupScoreCircuit<F, const DIFF_BITS: usize = VALUE_BITS> =
+    ScoreCircuit<F, DIFF_BITS, LookupRangeConfig>;
+
+impl<F: PrimeField, const DIFF_BITS: usize, R: RangeCheck<F>> Default
+    for ScoreCircuit<F, DIFF_BITS, R>
+{
+    fn default() -> Self {
+        Self {
+            score: Value::unknown(),
+            threshold: Value::unknown(),
+            old_state_root: [Value::unknown(); STATE_ROOT_LIMBS],
+            nonce: Value::unknown(),
+            blinding: Value::unknown(),
+            record_id: Value::unknown(),
+            record_salt: Value::unknown(),
+            dataset_siblings: [Value::unknown(); DATASET_DEPTH],
+            dataset_index: Value::unknown(),
+            range: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, const DIFF_BITS: usize, R: RangeCheck<F>> ScoreCircuit<F, DIFF_BITS, R> {
+    pub fn new(
+        score: u64,
+        threshold: u64,
+        old_state_root: [u8; 32],
+        nonce: u64,
+        blinding: F,
+        record: &DatasetRecord<F>,
+    ) -> Self {
+        Self {
+            score: Value::known(F::from(score)),
+            threshold: Value::known(F::from(threshold)),
+            old_state_root: state_root_limbs::<F>(&old_state_root).map(Value::known),
+            nonce: Value::known(F::from(nonce)),
+            blinding: Value::known(blinding),
+            record_id: Value::known(record.id),
+            record_salt: Value::known(record.salt),
+            dataset_siblings: record.siblings.map(Value::known),
+            dataset_index: Value::known(record.index),
+            range: PhantomData,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
-pub struct ScoreConfig {
+pub struct ScoreConfig<R> {
     score: Column<Advice>,
     diff: Column<Advice>,
-    diff_bits: [Column<Advice>; DIFF_BITS],
     threshold_advice: Column<Advice>,
-    threshold: Column<Instance>,
+    state_advice: Column<Advice>,
+    blinding: Column<Advice>,
+    instance: Column<Instance>,
     selector: Selector,
+    range: R,
+    poseidon: PoseidonConfig,
+    merkle: MerkleConfig,
 }
 
-impl<F: PrimeField> Circuit<F> for ScoreCircuit<F> {
-    type Config = ScoreConfig;
+impl<F: PrimeField, const DIFF_BITS: usize, R: RangeCheck<F>> Circuit<F>
+    for ScoreCircuit<F, DIFF_BITS, R>
+{
+    type Config = ScoreConfig<R>;
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
-        Self {
-            score: Value::unknown(),
-            threshold: Value::unknown(),
-        }
+        Self::default()
     }
 
     fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        const {
+            assert!(
+                DIFF_BITS > 0
+                    && DIFF_BITS <= VALUE_BITS
+                    && DIFF_BITS.is_multiple_of(R::CHUNK_BITS),
+                "DIFF_BITS must be a multiple of the chunk width in (0, 64]"
+            )
+        };
+
         let score = cs.advice_column();
         let diff = cs.advice_column();
-        let threshold = cs.instance_column();
+        let instance = cs.instance_column();
         let selector = cs.selector();
         let threshold_advice = cs.advice_column();
-
-        let diff_bits = [(); DIFF_BITS].map(|_| cs.advice_column());
+        let state_advice = cs.advice_column();
+        let blinding = cs.advice_column();
 
         cs.enable_equality(threshold_advice);
+        cs.enable_equality(state_advice);
+        cs.enable_equality(blinding);
         cs.enable_equality(score);
         cs.enable_equality(diff);
-        cs.enable_equality(threshold);
-
-        for bit in diff_bits.iter() {
-            cs.enable_equality(*bit);
-
-            // bit ∈ {0,1} (gated by selector)
-            cs.create_gate("bit is boolean", |meta| {
-                let s = meta.query_selector(selector);
-                let b = meta.query_advice(*bit, Rotation::cur());
-                vec![s * b.clone() * (Expression::Constant(F::ONE) - b)]
-            });
-        }
+        cs.enable_equality(instance);
 
         // threshold = score + diff
         cs.create_gate("threshold = score + diff", |meta| {
             let s = meta.query_selector(selector);
             let score = meta.query_advice(score, Rotation::cur());
             let diff = meta.query_advice(diff, Rotation::cur());
-            let threshold = meta.query_instance(threshold, Rotation::cur());
+            let threshold = meta.query_advice(threshold_advice, Rotation::cur());
 
             vec![s * (threshold - score - diff)]
         });
 
-        // diff == sum(bits * 2^i)
-        cs.create_gate("diff reconstruction", |meta| {
-            let s = meta.query_selector(selector);
-            let diff = meta.query_advice(diff, Rotation::cur());
-
-            let reconstructed = diff_bits.iter().enumerate().fold(
-                Expression::Constant(F::ZERO),
-                |acc, (i, bit)| {
-                    acc
-                        + meta.query_advice(*bit, Rotation::cur())
-                            * Expression::Constant(F::from_u128(1u128 << i))
-                },
-            );
-
-            vec![s * (diff - reconstructed)]
-        });
+        let range = R::configure(cs);
+        let poseidon = PoseidonConfig::configure(cs);
+        let merkle = MerkleConfig::configure(cs);
 
         ScoreConfig {
             score,
             diff,
-            diff_bits,
             threshold_advice,
-            threshold,
+            state_advice,
+            blinding,
+            instance,
             selector,
+            range,
+            poseidon,
+            merkle,
         }
     }
 
@@ -110,12 +361,14 @@ impl<F: PrimeField> Circuit<F> for ScoreCircuit<F> {
         config: Self::Config,
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
-        let threshold_cell = layouter.assign_region(
+        config.range.load(&mut layouter)?;
+
+        let (score_cell, diff_cell, threshold_cell, blinding_cell, id_cell, salt_cell) = layouter.assign_region(
             || "score <= threshold",
             |mut region| {
                 config.selector.enable(&mut region, 0)?;
 
-                region.assign_advice(
+                let score_cell = region.assign_advice(
                     || "score",
                     config.score,
                     0,
@@ -127,34 +380,13 @@ impl<F: PrimeField> Circuit<F> for ScoreCircuit<F> {
                     .zip(self.score)
                     .map(|(t, s)| t - s);
 
-                region.assign_advice(
+                let diff_cell = region.assign_advice(
                     || "diff",
                     config.diff,
                     0,
                     || diff_value,
                 )?;
 
-                // Assign diff bits
-                for i in 0..DIFF_BITS {
-                    let bit = diff_value.map(|diff| {
-                        let bytes = diff.to_repr();
-                        let mut acc = 0u64;
-
-                        for (j, b) in bytes.as_ref().iter().take(8).enumerate() {
-                            acc |= (*b as u64) << (8 * j);
-                        }
-
-                        (acc >> i) & 1
-                    });
-
-                    region.assign_advice(
-                        || format!("diff bit {}", i),
-                        config.diff_bits[i],
-                        0,
-                        || bit.map(F::from),
-                    )?;
-                }
-
                 // assign threshold into advice
                 let threshold_cell = region.assign_advice(
                     || "threshold advice",
@@ -163,15 +395,78 @@ impl<F: PrimeField> Circuit<F> for ScoreCircuit<F> {
                     || self.threshold,
                 )?;
 
-                Ok(threshold_cell)
+                let blinding_cell = region.assign_advice(
+                    || "blinding",
+                    config.blinding,
+                    0,
+                    || self.blinding,
+                )?;
+
+                let id_cell = region.assign_advice(
+                    || "record id",
+                    config.blinding,
+                    1,
+                    || self.record_id,
+                )?;
+
+                let salt_cell = region.assign_advice(
+                    || "record salt",
+                    config.blinding,
+                    2,
+                    || self.record_salt,
+                )?;
+
+                Ok((score_cell, diff_cell, threshold_cell, blinding_cell, id_cell, salt_cell))
             },
         )?;
 
-        // constrain advice cell to instance column
+        // diff >= 0, and no wrap-around through out-of-range operands
+        config.range.assign(layouter.namespace(|| "diff range"), &diff_cell, DIFF_BITS)?;
+        config.range.assign(layouter.namespace(|| "score range"), &score_cell, VALUE_BITS)?;
+        config.range.assign(
+            layouter.namespace(|| "threshold range"),
+            &threshold_cell,
+            VALUE_BITS,
+        )?;
+
+        // constrain advice cells to instance column
         layouter.constrain_instance(
             threshold_cell.cell(),
-            config.threshold,
-            0,
+            config.instance,
+            THRESHOLD_ROW,
+        )?;
+
+        // record_leaf(id, score, salt) is in the dataset
+        let leaf = config.poseidon.hash(
+            layouter.namespace(|| "record leaf"),
+            &[id_cell, score_cell.clone(), salt_cell],
+        )?;
+        let dataset_root = config.merkle.root(
+            layouter.namespace(|| "dataset membership"),
+            &config.poseidon,
+            &leaf,
+            &self.dataset_siblings,
+            self.dataset_index,
+        )?;
+
+        layouter.constrain_instance(dataset_root.cell(), config.instance, DATASET_ROOT_ROW)?;
+
+        // commitment = H(score, blinding)
+        let commitment = config.poseidon.hash(
+            layouter.namespace(|| "score commitment"),
+            &[score_cell, blinding_cell],
+        )?;
+
+        layouter.constrain_instance(commitment.cell(), config.instance, COMMITMENT_ROW)?;
+
+        bind_state(
+            &mut layouter,
+            config.state_advice,
+            config.instance,
+            &config.poseidon,
+            &self.old_state_root,
+            self.nonce,
+            commitment,
         )?;
 
         Ok(())
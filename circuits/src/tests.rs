@@ -4,25 +4,106 @@ Do Not Run These Tests
 */
 
 use halo2_proofs::{
+    circuit::Value,
     dev::MockProver,
     pasta::Fp,
 };
 
-use crate::score_circuit::ScoreCircuit;
+use halo2curves::ff::PrimeField;
+
+use crate::halo2_artifacts::{Halo2Artifacts, Halo2ArtifactsError};
+use crate::merkle::{merkle_path, merkle_root};
+use crate::poseidon;
+use crate::score_circuit::{
+    commitment_from_bytes, commitment_to_bytes, public_instances, record_leaf,
+    score_commitment, DatasetRecord, LookupScoreCircuit, ScoreCircuit, DATASET_DEPTH,
+    NEW_STATE_ROOT_ROW,
+};
+
+// Poseidon takes one row per round: commitment, state transition,
+// record leaf and every dataset level
+const K: u32 = 10;
+const ROOT: [u8; 32] = [7u8; 32];
+const NONCE: u64 = 1;
+
+fn blinding() -> Fp {
+    Fp::from(0xB11D)
+}
+
+fn commitment(score: u64) -> Fp {
+    score_commitment(score, blinding())
+}
+
+/// Scored record at an explicit position of a fixed dataset
+fn record() -> DatasetRecord<Fp> {
+    DatasetRecord {
+        id: Fp::from(RECORD_INDEX as u64),
+        salt: Fp::from(0x5A17),
+        siblings: std::array::from_fn(|level| Fp::from(level as u64 + 1)),
+        index: RECORD_INDEX as u64,
+    }
+}
+
+fn dataset_root(score: u64) -> Fp {
+    record().root(score)
+}
+
+/// Circuit with arbitrary (possibly malicious) field witnesses
+fn crafted_circuit(score: Fp, threshold: Fp) -> ScoreCircuit<Fp> {
+    ScoreCircuit {
+        score: Value::known(score),
+        threshold: Value::known(threshold),
+        ..ScoreCircuit::new(0, 0, ROOT, NONCE, blinding(), &record())
+    }
+}
+
+/// Instances matching the crafted witnesses, commitment included
+fn crafted_instances(score: Fp, threshold: Fp) -> Vec<Vec<Fp>> {
+    let commitment = poseidon::hash(&[score, blinding()]);
+    let record = record();
+    let leaf = poseidon::hash(&[record.id, score, record.salt]);
+    let dataset_root = merkle_root(leaf, &record.siblings, record.index);
+    let mut instances = public_instances::<Fp>(0, &ROOT, NONCE, commitment, dataset_root);
+    instances[0] = threshold;
+    vec![instances]
+}
 
 #[test]
 fn score_below_threshold_passes() {
-    let threshold = Fp::from(10);
+    let circuit = ScoreCircuit::<Fp>::new(5, 10, ROOT, NONCE, blinding(), &record());
 
-    let circuit = ScoreCircuit::<Fp> {
-        score: halo2_proofs::circuit::Value::known(Fp::from(5)),
-        threshold: halo2_proofs::circuit::Value::known(threshold),
-    };
+    let prover = MockProver::run(
+        K,
+        &circuit,
+        vec![public_instances(10, &ROOT, NONCE, commitment(5), dataset_root(5))], // 👈 PUBLIC INPUT
+    )
+    .unwrap();
+
+    prover.assert_satisfied();
+}
+
+#[test]
+fn score_above_threshold_is_rejected() {
+    let circuit = ScoreCircuit::<Fp>::new(15, 10, ROOT, NONCE, blinding(), &record());
 
     let prover = MockProver::run(
-        4,
+        K,
         &circuit,
-        vec![vec![threshold]], // 👈 PUBLIC INPUT
+        vec![public_instances(10, &ROOT, NONCE, commitment(15), dataset_root(15))],
+    )
+    .unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn full_u64_range_passes_with_default_width() {
+    let circuit = ScoreCircuit::<Fp>::new(0, u64::MAX, ROOT, NONCE, blinding(), &record());
+
+    let prover = MockProver::run(
+        K,
+        &circuit,
+        vec![public_instances(u64::MAX, &ROOT, NONCE, commitment(0), dataset_root(0))],
     )
     .unwrap();
 
@@ -30,21 +111,370 @@ fn score_below_threshold_passes() {
 }
 
 #[test]
-fn score_above_threshold_is_satisfiable_without_score_binding() {
-    let threshold = Fp::from(10);
+fn diff_beyond_configured_width_is_rejected() {
+    let circuit = ScoreCircuit::<Fp, 16>::new(0, 1 << 16, ROOT, NONCE, blinding(), &record());
+
+    let prover = MockProver::run(
+        K,
+        &circuit,
+        vec![public_instances(1 << 16, &ROOT, NONCE, commitment(0), dataset_root(0))],
+    )
+    .unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn wrapped_negative_score_is_rejected() {
+    // score = -5 makes diff = 15, which alone fits the diff range
+    let circuit = crafted_circuit(-Fp::from(5), Fp::from(10));
+
+    let prover = MockProver::run(K, &circuit, crafted_instances(-Fp::from(5), Fp::from(10))).unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn out_of_range_operands_are_rejected() {
+    // both operands above 2^64 with a small, valid-looking diff
+    let base = Fp::from_u128(1u128 << 64);
+    let circuit = crafted_circuit(base + Fp::from(5), base + Fp::from(10));
+
+    let prover = MockProver::run(
+        K,
+        &circuit,
+        crafted_instances(base + Fp::from(5), base + Fp::from(10)),
+    )
+    .unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn wrong_nonce_instance_is_rejected() {
+    let circuit = ScoreCircuit::<Fp>::new(5, 10, ROOT, NONCE, blinding(), &record());
+
+    let prover = MockProver::run(
+        K,
+        &circuit,
+        vec![public_instances(10, &ROOT, NONCE + 1, commitment(5), dataset_root(5))],
+    )
+    .unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn wrong_state_root_instance_is_rejected() {
+    let circuit = ScoreCircuit::<Fp>::new(5, 10, ROOT, NONCE, blinding(), &record());
+
+    let mut other_root = ROOT;
+    other_root[31] ^= 1;
+
+    let prover = MockProver::run(
+        K,
+        &circuit,
+        vec![public_instances(10, &other_root, NONCE, commitment(5), dataset_root(5))],
+    )
+    .unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+/* ---------------- Score commitment ---------------- */
+
+#[test]
+fn commitment_to_other_score_is_rejected() {
+    let circuit = ScoreCircuit::<Fp>::new(5, 10, ROOT, NONCE, blinding(), &record());
+
+    let prover = MockProver::run(
+        K,
+        &circuit,
+        vec![public_instances(10, &ROOT, NONCE, commitment(6), dataset_root(5))],
+    )
+    .unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn commitment_under_other_blinding_is_rejected() {
+    let circuit = ScoreCircuit::<Fp>::new(5, 10, ROOT, NONCE, Fp::from(1), &record());
+
+    let prover = MockProver::run(
+        K,
+        &circuit,
+        vec![public_instances(10, &ROOT, NONCE, commitment(5), dataset_root(5))],
+    )
+    .unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn blinding_hides_equal_scores() {
+    assert_ne!(score_commitment(5, Fp::from(1)), score_commitment(5, Fp::from(2)));
+    assert_eq!(score_commitment(5, blinding()), commitment(5));
+}
+
+#[test]
+fn commitment_bytes_round_trip() {
+    let bytes = commitment_to_bytes(&commitment(5));
+
+    assert_eq!(commitment_from_bytes::<Fp>(&bytes), Some(commitment(5)));
+    assert_eq!(commitment_from_bytes::<Fp>(&[0xFF; 32]), None);
+}
+
+/* ---------------- State transition ---------------- */
+
+#[test]
+fn forged_new_state_root_is_rejected() {
+    let circuit = ScoreCircuit::<Fp>::new(5, 10, ROOT, NONCE, blinding(), &record());
+
+    let mut instances = public_instances(10, &ROOT, NONCE, commitment(5), dataset_root(5));
+    instances[NEW_STATE_ROOT_ROW] = Fp::from(42);
+
+    let prover = MockProver::run(K, &circuit, vec![instances]).unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn new_state_root_depends_on_every_input() {
+    let root = public_instances(10, &ROOT, NONCE, commitment(5), dataset_root(5))[NEW_STATE_ROOT_ROW];
 
-    let circuit = ScoreCircuit::<Fp> {
-        score: halo2_proofs::circuit::Value::known(Fp::from(15)),
-        threshold: halo2_proofs::circuit::Value::known(threshold),
+    let mut other_root = ROOT;
+    other_root[0] ^= 1;
+
+    for other in [
+        public_instances(10, &other_root, NONCE, commitment(5), dataset_root(5)),
+        public_instances(10, &ROOT, NONCE + 1, commitment(5), dataset_root(5)),
+        public_instances(10, &ROOT, NONCE, commitment(6), dataset_root(6)),
+    ] {
+        assert_ne!(other[NEW_STATE_ROOT_ROW], root);
+    }
+}
+
+/* ---------------- Dataset membership ---------------- */
+
+const RECORD_INDEX: usize = 37;
+
+/// Dataset of 2^DATASET_DEPTH records, record i scored i, except the
+/// scored record at RECORD_INDEX
+fn dataset(score: u64) -> (Fp, DatasetRecord<Fp>) {
+    let salt = |i: u64| Fp::from(0x5A17 + i);
+    let mut leaves: Vec<Fp> = (0..1u64 << DATASET_DEPTH)
+        .map(|i| record_leaf(Fp::from(i), i, salt(i)))
+        .collect();
+    let index = RECORD_INDEX as u64;
+    leaves[RECORD_INDEX] = record_leaf(Fp::from(index), score, salt(index));
+
+    let (root, siblings) = merkle_path(&leaves, RECORD_INDEX);
+    let record = DatasetRecord {
+        id: Fp::from(index),
+        salt: salt(index),
+        siblings: siblings.try_into().unwrap(),
+        index,
     };
+    (root, record)
+}
+
+#[test]
+fn record_in_dataset_passes() {
+    let (root, record) = dataset(5);
+    let circuit = ScoreCircuit::<Fp>::new(5, 10, ROOT, NONCE, blinding(), &record);
+
+    let prover = MockProver::run(
+        K,
+        &circuit,
+        vec![public_instances(10, &ROOT, NONCE, commitment(5), root)],
+    )
+    .unwrap();
+
+    prover.assert_satisfied();
+}
+
+#[test]
+fn record_at_other_position_is_rejected() {
+    let (root, record) = dataset(5);
+    let record = DatasetRecord { index: record.index + 1, ..record };
+    let circuit = ScoreCircuit::<Fp>::new(5, 10, ROOT, NONCE, blinding(), &record);
+
+    let prover = MockProver::run(
+        K,
+        &circuit,
+        vec![public_instances(10, &ROOT, NONCE, commitment(5), root)],
+    )
+    .unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn record_not_in_dataset_is_rejected() {
+    // the dataset holds score 6 at RECORD_INDEX, the prover claims 5
+    let (root, record) = dataset(6);
+    let circuit = ScoreCircuit::<Fp>::new(5, 10, ROOT, NONCE, blinding(), &record);
+
+    let prover = MockProver::run(
+        K,
+        &circuit,
+        vec![public_instances(10, &ROOT, NONCE, commitment(5), root)],
+    )
+    .unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn other_record_with_same_score_is_rejected() {
+    // record 5 of the dataset is scored 5 too, but it is not the prover's
+    let (root, record) = dataset(5);
+    let record = DatasetRecord { id: Fp::from(5), ..record };
+    let circuit = ScoreCircuit::<Fp>::new(5, 10, ROOT, NONCE, blinding(), &record);
+
+    let prover = MockProver::run(
+        K,
+        &circuit,
+        vec![public_instances(10, &ROOT, NONCE, commitment(5), root)],
+    )
+    .unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn record_leaf_depends_on_id_and_salt() {
+    let leaf = record_leaf(Fp::from(1), 5, Fp::from(2));
+
+    assert_ne!(leaf, record_leaf(Fp::from(3), 5, Fp::from(2)));
+    assert_ne!(leaf, record_leaf(Fp::from(1), 5, Fp::from(4)));
+}
+
+/* ---------------- Lookup layout ---------------- */
+
+// the limb table needs 2^LIMB_BITS usable rows, Poseidon needs more
+const LOOKUP_K: u32 = 10;
+
+#[test]
+fn lookup_score_below_threshold_passes() {
+    let circuit = LookupScoreCircuit::<Fp>::new(5, 10, ROOT, NONCE, blinding(), &record());
+
+    let prover = MockProver::run(
+        LOOKUP_K,
+        &circuit,
+        vec![public_instances(10, &ROOT, NONCE, commitment(5), dataset_root(5))],
+    )
+    .unwrap();
+
+    prover.assert_satisfied();
+}
+
+#[test]
+fn lookup_full_u64_range_passes() {
+    let circuit = LookupScoreCircuit::<Fp>::new(0, u64::MAX, ROOT, NONCE, blinding(), &record());
 
     let prover = MockProver::run(
-        4,
+        LOOKUP_K,
         &circuit,
-        vec![vec![threshold]],
+        vec![public_instances(u64::MAX, &ROOT, NONCE, commitment(0), dataset_root(0))],
     )
     .unwrap();
 
     prover.assert_satisfied();
+}
+
+#[test]
+fn lookup_score_above_threshold_is_rejected() {
+    let circuit = LookupScoreCircuit::<Fp>::new(15, 10, ROOT, NONCE, blinding(), &record());
+
+    let prover = MockProver::run(
+        LOOKUP_K,
+        &circuit,
+        vec![public_instances(10, &ROOT, NONCE, commitment(15), dataset_root(15))],
+    )
+    .unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn lookup_wrapped_negative_score_is_rejected() {
+    let circuit = LookupScoreCircuit::<Fp> {
+        score: Value::known(-Fp::from(5)),
+        threshold: Value::known(Fp::from(10)),
+        ..LookupScoreCircuit::new(0, 0, ROOT, NONCE, blinding(), &record())
+    };
+
+    let prover = MockProver::run(
+        LOOKUP_K,
+        &circuit,
+        crafted_instances(-Fp::from(5), Fp::from(10)),
+    )
+    .unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn lookup_diff_beyond_configured_width_is_rejected() {
+    let circuit = LookupScoreCircuit::<Fp, 8>::new(0, 1 << 8, ROOT, NONCE, blinding(), &record());
+
+    let prover = MockProver::run(
+        LOOKUP_K,
+        &circuit,
+        vec![public_instances(1 << 8, &ROOT, NONCE, commitment(0), dataset_root(0))],
+    )
+    .unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+/* ---------------- Artifact files ---------------- */
+
+fn artifact_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("zkcg-{}-{}.bin", name, std::process::id()))
+}
+
+#[test]
+fn artifacts_round_trip_through_file() {
+    let path = artifact_path("round-trip");
+    let artifacts = Halo2Artifacts::for_score_circuit();
+
+    artifacts.write_to(&path).unwrap();
+    let loaded = Halo2Artifacts::read_from(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.fingerprint(), artifacts.fingerprint());
+    assert_eq!(loaded.vk_digest(), artifacts.vk_digest());
+}
+
+#[test]
+fn corrupted_artifact_file_is_rejected() {
+    let path = artifact_path("corrupt");
+    Halo2Artifacts::for_score_circuit().write_to(&path).unwrap();
+
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[100] ^= 0xFF;
+    std::fs::write(&path, bytes).unwrap();
+
+    let result = Halo2Artifacts::read_from(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(Halo2ArtifactsError::Corrupt)));
+}
+
+#[test]
+fn artifacts_for_other_circuit_are_rejected() {
+    let path = artifact_path("mismatch");
+    let params = halo2_proofs::poly::commitment::Params::new(LOOKUP_K);
+    Halo2Artifacts::for_circuit::<LookupScoreCircuit<halo2curves::bn256::Fr>>(params)
+        .unwrap()
+        .write_to(&path)
+        .unwrap();
+
+    let result = Halo2Artifacts::read_from(&path);
+    std::fs::remove_file(&path).unwrap();
 
+    assert!(matches!(result, Err(Halo2ArtifactsError::CircuitMismatch)));
 }
@@ -0,0 +1,106 @@
+use halo2_proofs::{
+    circuit::Value,
+    dev::MockProver,
+    pasta::Fp,
+};
+
+use crate::weighted_score_circuit::{
+    features_commitment, weighted_public_instances, WeightedScoreCircuit,
+};
+
+const K: u32 = 9;
+const ROOT: [u8; 32] = [7u8; 32];
+const NONCE: u64 = 1;
+
+// Q16.16 features, Q8.8 weights => Q24.24 sum
+const FEATURES: [u64; 3] = [3 << 16, 1 << 15, 10 << 16];
+const WEIGHTS: [u64; 3] = [2 << 8, 4 << 8, 1 << 7];
+
+fn weighted_sum() -> u64 {
+    FEATURES.iter().zip(WEIGHTS.iter()).map(|(f, w)| f * w).sum()
+}
+
+fn blinding() -> Fp {
+    Fp::from(0xB11D)
+}
+
+fn instances(threshold: u64, nonce: u64, weights: &[u64]) -> Vec<Vec<Fp>> {
+    let commitment = features_commitment(&FEATURES, blinding());
+    vec![weighted_public_instances(threshold, &ROOT, nonce, commitment, weights)]
+}
+
+fn run(circuit: &WeightedScoreCircuit<Fp, 3>, threshold: u64, weights: &[u64]) -> MockProver<Fp> {
+    MockProver::run(K, circuit, instances(threshold, NONCE, weights)).unwrap()
+}
+
+#[test]
+fn weighted_sum_at_threshold_passes() {
+    let threshold = weighted_sum();
+    let circuit = WeightedScoreCircuit::<Fp, 3>::new(FEATURES, WEIGHTS, threshold, ROOT, NONCE, blinding());
+
+    run(&circuit, threshold, &WEIGHTS).assert_satisfied();
+}
+
+#[test]
+fn weighted_sum_above_threshold_is_rejected() {
+    let threshold = weighted_sum() - 1;
+    let circuit = WeightedScoreCircuit::<Fp, 3>::new(FEATURES, WEIGHTS, threshold, ROOT, NONCE, blinding());
+
+    assert!(run(&circuit, threshold, &WEIGHTS).verify().is_err());
+}
+
+#[test]
+fn weights_other_than_public_ones_are_rejected() {
+    // prover lowers a weight to get under the threshold
+    let threshold = weighted_sum() - 1;
+    let mut lowered = WEIGHTS;
+    lowered[2] = 0;
+    let circuit = WeightedScoreCircuit::<Fp, 3>::new(FEATURES, lowered, threshold, ROOT, NONCE, blinding());
+
+    assert!(run(&circuit, threshold, &WEIGHTS).verify().is_err());
+}
+
+#[test]
+fn wrapped_negative_feature_is_rejected() {
+    // feature = -1 pulls the sum down, keeping diff small
+    let threshold = weighted_sum();
+    let circuit = WeightedScoreCircuit::<Fp, 3> {
+        features: [
+            Value::known(Fp::from(FEATURES[0])),
+            Value::known(-Fp::one()),
+            Value::known(Fp::from(FEATURES[2])),
+        ],
+        ..WeightedScoreCircuit::new(FEATURES, WEIGHTS, threshold, ROOT, NONCE, blinding())
+    };
+
+    assert!(run(&circuit, threshold, &WEIGHTS).verify().is_err());
+}
+
+#[test]
+fn wrong_nonce_instance_is_rejected() {
+    let threshold = weighted_sum();
+    let circuit = WeightedScoreCircuit::<Fp, 3>::new(FEATURES, WEIGHTS, threshold, ROOT, NONCE, blinding());
+
+    let prover = MockProver::run(K, &circuit, instances(threshold, NONCE + 1, &WEIGHTS)).unwrap();
+
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn commitment_to_other_features_is_rejected() {
+    let threshold = weighted_sum();
+    let circuit = WeightedScoreCircuit::<Fp, 3>::new(FEATURES, WEIGHTS, threshold, ROOT, NONCE, blinding());
+
+    let mut other = FEATURES;
+    other[0] -= 1;
+    let commitment = features_commitment(&other, blinding());
+
+    let prover = MockProver::run(
+        K,
+        &circuit,
+        vec![weighted_public_instances(threshold, &ROOT, NONCE, commitment, &WEIGHTS)],
+    )
+    .unwrap();
+
+    assert!(prover.verify().is_err());
+}
@@ -0,0 +1,352 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector,
+    },
+    poly::Rotation,
+};
+
+use halo2curves::ff::PrimeField;
+
+use crate::poseidon::{self, PoseidonConfig};
+use crate::range_check::{BitRangeConfig, RangeCheck};
+use crate::score_circuit::{
+    bind_state, state_instances, state_root_limbs, COMMITMENT_ROW, NEW_STATE_ROOT_ROW,
+    STATE_ROOT_LIMBS, THRESHOLD_ROW, VALUE_BITS,
+};
+
+/// Range width of each private feature
+pub const FEATURE_BITS: usize = 32;
+
+/// Range width of each public weight
+pub const WEIGHT_BITS: usize = 16;
+
+/// First instance row carrying the weight vector
+pub const WEIGHTS_ROW: usize = NEW_STATE_ROOT_ROW + 1;
+
+/// Poseidon commitment to the private features: H(features.., blinding)
+pub fn features_commitment<F: PrimeField>(features: &[u64], blinding: F) -> F {
+    let mut inputs: Vec<F> = features.iter().map(|f| F::from(*f)).collect();
+    inputs.push(blinding);
+    poseidon::hash(&inputs)
+}
+
+/// Public instance column for WeightedScoreCircuit, in layout order:
+/// the ScoreCircuit layout up to `NEW_STATE_ROOT_ROW` (no dataset root),
+/// followed by the weights
+pub fn weighted_public_instances<F: PrimeField>(
+    threshold: u64,
+    old_state_root: &[u8; 32],
+    nonce: u64,
+    commitment: F,
+    weights: &[u64],
+) -> Vec<F> {
+    let mut instances = state_instances::<F>(threshold, old_state_root, nonce, commitment);
+    instances.extend(weights.iter().map(|w| F::from(*w)));
+    instances
+}
+
+/// Enforces: sum(features[i] * weights[i]) <= threshold
+///
+/// Features are private fixed-point values in [0, 2^32), weights are
+/// public fixed-point values in [0, 2^16). The weighted sum is computed
+/// in-circuit at the product scale of the two (e.g. Q16.16 features with
+/// Q8.8 weights give a Q24.24 sum), so `threshold` must be given at that
+/// scale too. With N <= 2^16 the sum cannot exceed 2^64.
+///
+/// Constraint model:
+///   acc_0 = features[0] * weights[0]
+///   acc_i = acc_{i-1} + features[i] * weights[i]
+///   threshold = acc_{N-1} + diff
+///   threshold, diff ∈ [0, 2^64)
+///
+/// Like ScoreCircuit, the proof is bound to `old_state_root` and `nonce`,
+/// computes the next state root, and exposes a Poseidon commitment to the features (see
+/// `features_commitment`) at `COMMITMENT_ROW`.
+#[derive(Clone)]
+pub struct WeightedScoreCircuit<F: PrimeField, const N: usize, R: RangeCheck<F> = BitRangeConfig> {
+    pub features: [Value<F>; N],
+    pub weights: [Value<F>; N],
+    pub threshold: Value<F>,
+    pub old_state_root: [Value<F>; STATE_ROOT_LIMBS],
+    pub nonce: Value<F>,
+    pub blinding: Value<F>,
+    pub(crate) range: PhantomData<R>,
+}
+
+impl<F: PrimeField, const N: usize, R: RangeCheck<F>> Default for WeightedScoreCircuit<F, N, R> {
+    fn default() -> Self {
+        Self {
+            features: [Value::unknown(); N],
+            weights: [Value::unknown(); N],
+            threshold: Value::unknown(),
+            old_state_root: [Value::unknown(); STATE_ROOT_LIMBS],
+            nonce: Value::unknown(),
+            blinding: Value::unknown(),
+            range: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, const N: usize, R: RangeCheck<F>> WeightedScoreCircuit<F, N, R> {
+    pub fn new(
+        features: [u64; N],
+        weights: [u64; N],
+        threshold: u64,
+        old_state_root: [u8; 32],
+        nonce: u64,
+        blinding: F,
+    ) -> Self {
+        Self {
+            features: features.map(|f| Value::known(F::from(f))),
+            weights: weights.map(|w| Value::known(F::from(w))),
+            threshold: Value::known(F::from(threshold)),
+            old_state_root: state_root_limbs::<F>(&old_state_root).map(Value::known),
+            nonce: Value::known(F::from(nonce)),
+            blinding: Value::known(blinding),
+            range: PhantomData,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WeightedScoreConfig<R> {
+    feature: Column<Advice>,
+    weight: Column<Advice>,
+    acc: Column<Advice>,
+    diff: Column<Advice>,
+    threshold_advice: Column<Advice>,
+    state_advice: Column<Advice>,
+    blinding: Column<Advice>,
+    instance: Column<Instance>,
+    q_first: Selector,
+    q_step: Selector,
+    q_cmp: Selector,
+    range: R,
+    poseidon: PoseidonConfig,
+}
+
+impl<F: PrimeField, const N: usize, R: RangeCheck<F>> Circuit<F>
+    for WeightedScoreCircuit<F, N, R>
+{
+    type Config = WeightedScoreConfig<R>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
+        const {
+            assert!(
+                N > 0 && N <= 1 << 16,
+                "WeightedScoreCircuit needs between 1 and 2^16 features"
+            )
+        };
+
+        let feature = cs.advice_column();
+        let weight = cs.advice_column();
+        let acc = cs.advice_column();
+        let diff = cs.advice_column();
+        let threshold_advice = cs.advice_column();
+        let state_advice = cs.advice_column();
+        let blinding = cs.advice_column();
+        let instance = cs.instance_column();
+        let q_first = cs.selector();
+        let q_step = cs.selector();
+        let q_cmp = cs.selector();
+
+        cs.enable_equality(feature);
+        cs.enable_equality(weight);
+        cs.enable_equality(diff);
+        cs.enable_equality(threshold_advice);
+        cs.enable_equality(state_advice);
+        cs.enable_equality(blinding);
+        cs.enable_equality(instance);
+
+        // acc = feature * weight
+        cs.create_gate("first term", |meta| {
+            let s = meta.query_selector(q_first);
+            let f = meta.query_advice(feature, Rotation::cur());
+            let w = meta.query_advice(weight, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+
+            vec![s * (acc - f * w)]
+        });
+
+        // acc = acc_prev + feature * weight
+        cs.create_gate("next term", |meta| {
+            let s = meta.query_selector(q_step);
+            let f = meta.query_advice(feature, Rotation::cur());
+            let w = meta.query_advice(weight, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+
+            vec![s * (acc_cur - acc_prev - f * w)]
+        });
+
+        // threshold = sum + diff
+        cs.create_gate("threshold = sum + diff", |meta| {
+            let s = meta.query_selector(q_cmp);
+            let sum = meta.query_advice(acc, Rotation::cur());
+            let diff = meta.query_advice(diff, Rotation::cur());
+            let threshold = meta.query_advice(threshold_advice, Rotation::cur());
+
+            vec![s * (threshold - sum - diff)]
+        });
+
+        let range = R::configure(cs);
+        let poseidon = PoseidonConfig::configure(cs);
+
+        WeightedScoreConfig {
+            feature,
+            weight,
+            acc,
+            diff,
+            threshold_advice,
+            state_advice,
+            blinding,
+            instance,
+            q_first,
+            q_step,
+            q_cmp,
+            range,
+            poseidon,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.range.load(&mut layouter)?;
+
+        let (feature_cells, weight_cells, diff_cell, threshold_cell, blinding_cell) = layouter.assign_region(
+            || "weighted sum <= threshold",
+            |mut region| {
+                let mut feature_cells = Vec::with_capacity(N);
+                let mut weight_cells = Vec::with_capacity(N);
+                let mut acc = Value::known(F::ZERO);
+
+                for (row, (f, w)) in self.features.iter().zip(self.weights.iter()).enumerate() {
+                    if row == 0 {
+                        config.q_first.enable(&mut region, row)?;
+                    } else {
+                        config.q_step.enable(&mut region, row)?;
+                    }
+
+                    feature_cells.push(region.assign_advice(
+                        || format!("feature {}", row),
+                        config.feature,
+                        row,
+                        || *f,
+                    )?);
+
+                    weight_cells.push(region.assign_advice(
+                        || format!("weight {}", row),
+                        config.weight,
+                        row,
+                        || *w,
+                    )?);
+
+                    acc = acc + f.zip(*w).map(|(f, w)| f * w);
+
+                    region.assign_advice(
+                        || format!("running sum {}", row),
+                        config.acc,
+                        row,
+                        || acc,
+                    )?;
+                }
+
+                // compare on the last running-sum row
+                let last = N - 1;
+                config.q_cmp.enable(&mut region, last)?;
+
+                let diff_cell = region.assign_advice(
+                    || "diff",
+                    config.diff,
+                    last,
+                    || self.threshold.zip(acc).map(|(t, s)| t - s),
+                )?;
+
+                let threshold_cell = region.assign_advice(
+                    || "threshold advice",
+                    config.threshold_advice,
+                    last,
+                    || self.threshold,
+                )?;
+
+                let blinding_cell = region.assign_advice(
+                    || "blinding",
+                    config.blinding,
+                    0,
+                    || self.blinding,
+                )?;
+
+                Ok((feature_cells, weight_cells, diff_cell, threshold_cell, blinding_cell))
+            },
+        )?;
+
+        for (i, cell) in feature_cells.iter().enumerate() {
+            config.range.assign(
+                layouter.namespace(|| format!("feature {} range", i)),
+                cell,
+                FEATURE_BITS,
+            )?;
+        }
+
+        for (i, cell) in weight_cells.iter().enumerate() {
+            config.range.assign(
+                layouter.namespace(|| format!("weight {} range", i)),
+                cell,
+                WEIGHT_BITS,
+            )?;
+        }
+
+        // diff >= 0, and no wrap-around through an out-of-range threshold
+        config.range.assign(layouter.namespace(|| "diff range"), &diff_cell, VALUE_BITS)?;
+        config.range.assign(
+            layouter.namespace(|| "threshold range"),
+            &threshold_cell,
+            VALUE_BITS,
+        )?;
+
+        // constrain advice cells to instance column
+        layouter.constrain_instance(
+            threshold_cell.cell(),
+            config.instance,
+            THRESHOLD_ROW,
+        )?;
+
+        for (i, cell) in weight_cells.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), config.instance, WEIGHTS_ROW + i)?;
+        }
+
+        // commitment = H(features.., blinding)
+        let mut committed = feature_cells;
+        committed.push(blinding_cell);
+
+        let commitment = config.poseidon.hash(
+            layouter.namespace(|| "features commitment"),
+            &committed,
+        )?;
+
+        layouter.constrain_instance(commitment.cell(), config.instance, COMMITMENT_ROW)?;
+
+        bind_state(
+            &mut layouter,
+            config.state_advice,
+            config.instance,
+            &config.poseidon,
+            &self.old_state_root,
+            self.nonce,
+            commitment,
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use crate::types::Hash;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Levels of the dataset tree checked by the zkVM membership guest
+pub const DATASET_DEPTH: usize = 8;
+
+/// Leaf committing to a scored record: H(record id || score || salt)
+///
+/// The id ties the leaf to one record, so records sharing a score have
+/// distinct leaves; the secret salt keeps the score from being recovered
+/// by hashing every candidate value.
+pub fn record_leaf(id: u64, score: u64, salt: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(b"zkcg/dataset-leaf/v2");
+    hasher.update(id.to_le_bytes());
+    hasher.update(score.to_le_bytes());
+    hasher.update(salt);
+    hasher.finalize().into()
+}
+
+/// Parent node of the dataset tree: H(left || right)
+pub fn merkle_node(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(b"zkcg/dataset-node/v1");
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Sibling path from a leaf to the dataset root, leaf level first
+///
+/// Bit i of `index` is set when the running node is the right child at
+/// level i, as in `circuits::merkle::merkle_root`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatasetPath {
+    pub siblings: [Hash; DATASET_DEPTH],
+    pub index: u64,
+}
+
+/// A record's place in the dataset: its identity, salt and Merkle path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatasetRecord {
+    pub id: u64,
+    pub salt: Hash,
+    pub path: DatasetPath,
+}
+
+impl DatasetRecord {
+    /// Leaf of this record carrying `score`
+    pub fn leaf(&self, score: u64) -> Hash {
+        record_leaf(self.id, score, &self.salt)
+    }
+
+    /// Dataset root the path leads to from this record carrying `score`
+    pub fn root(&self, score: u64) -> Hash {
+        self.path.root(&self.leaf(score))
+    }
+}
+
+impl DatasetPath {
+    pub fn root(&self, leaf: &Hash) -> Hash {
+        self.siblings
+            .iter()
+            .enumerate()
+            .fold(*leaf, |node, (level, sibling)| {
+                if (self.index >> level) & 1 == 1 {
+                    merkle_node(sibling, &node)
+                } else {
+                    merkle_node(&node, sibling)
+                }
+            })
+    }
+}
@@ -21,4 +21,7 @@ pub enum ProtocolError {
 
     #[cfg_attr(feature = "std", error("commitment mismatch"))]
     CommitmentMismatch,
+
+    #[cfg_attr(feature = "std", error("state storage failed"))]
+    StorageFailure,
 }
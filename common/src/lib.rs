@@ -1,5 +1,9 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+pub mod dataset;
 pub mod state;
 pub mod errors;
 pub mod types;
+
+#[cfg(test)]
+mod tests;
@@ -1,5 +1,6 @@
 use crate::types::Hash;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProtocolState {
@@ -17,3 +18,17 @@ impl ProtocolState {
         }
     }
 }
+
+/// Canonical state transition: H(old_root || nonce || payload_commitment)
+///
+/// SHA-256 with a domain tag, so the zkVM guest can compute it in-proof.
+/// Backends whose circuits hash natively in their field (Halo2) define
+/// their own transition; see `ProofBackend::next_state_root`.
+pub fn next_state_root(old_state_root: &Hash, nonce: u64, payload_commitment: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(b"zkcg/state-transition/v1");
+    hasher.update(old_state_root);
+    hasher.update(nonce.to_le_bytes());
+    hasher.update(payload_commitment);
+    hasher.finalize().into()
+}
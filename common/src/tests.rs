@@ -0,0 +1,211 @@
+use crate::{
+    dataset::{merkle_node, record_leaf, DatasetPath, DatasetRecord},
+    state::next_state_root,
+    types::{
+        score_commitment, ChainInput, ChainJournal, ModelParams, ScoreInput, ZkVmInput,
+        ZkVmJournal, ZkVmOutput, MODEL_FEATURES, NO_DATASET, NO_MODEL,
+    },
+};
+
+fn journal() -> ZkVmJournal {
+    ZkVmJournal {
+        threshold: 0x0102_0304_0506_0708,
+        old_state_root: [0x11; 32],
+        nonce: 0x1112_1314_1516_1718,
+        payload_commitment: [0x22; 32],
+        new_state_root: [0x33; 32],
+        model_hash: [0x44; 32],
+        dataset_root: [0x55; 32],
+        output: ZkVmOutput { ok: true },
+    }
+}
+
+#[test]
+fn journal_round_trips() {
+    let journal = journal();
+    assert_eq!(ZkVmJournal::decode(&journal.encode()), Some(journal));
+}
+
+#[test]
+fn journal_encodes_in_commit_order() {
+    // the guest commits these bytes as-is; reordering fields breaks the verifier
+    let bytes = journal().encode();
+
+    assert_eq!(bytes[0..8], 0x0102_0304_0506_0708u64.to_le_bytes());
+    assert_eq!(bytes[8..40], [0x11; 32]);
+    assert_eq!(bytes[40..48], 0x1112_1314_1516_1718u64.to_le_bytes());
+    assert_eq!(bytes[48..80], [0x22; 32]);
+    assert_eq!(bytes[80..112], [0x33; 32]);
+    assert_eq!(bytes[112..144], [0x44; 32]);
+    assert_eq!(bytes[144..176], [0x55; 32]);
+    assert_eq!(bytes[176..], [1]);
+}
+
+#[test]
+fn journal_with_wrong_length_is_rejected() {
+    let bytes = journal().encode();
+
+    assert_eq!(ZkVmJournal::decode(&bytes[..ZkVmJournal::ENCODED_LEN - 1]), None);
+    assert_eq!(ZkVmJournal::decode(&[bytes.as_slice(), &[0]].concat()), None);
+}
+
+#[test]
+fn journal_with_non_boolean_ok_is_rejected() {
+    let mut bytes = journal().encode();
+    bytes[ZkVmJournal::ENCODED_LEN - 1] = 2;
+
+    assert_eq!(ZkVmJournal::decode(&bytes), None);
+}
+
+#[test]
+fn model_score_is_weighted_sum() {
+    let params = ModelParams { weights: [1, 2, 3, 4, 0, 0, 0, u16::MAX] };
+    let features = [10, 20, 30, 40, 99, 99, 99, 2];
+
+    assert_eq!(params.score(&features), 10 + 40 + 90 + 160 + 2 * u16::MAX as u64);
+}
+
+#[test]
+fn model_score_does_not_overflow() {
+    let params = ModelParams { weights: [u16::MAX; MODEL_FEATURES] };
+    let features = [u32::MAX; MODEL_FEATURES];
+
+    assert_eq!(
+        params.score(&features),
+        MODEL_FEATURES as u64 * u32::MAX as u64 * u16::MAX as u64
+    );
+}
+
+#[test]
+fn model_hash_depends_on_weights() {
+    let params = ModelParams { weights: [1; MODEL_FEATURES] };
+    let mut other = params;
+    other.weights[MODEL_FEATURES - 1] = 2;
+
+    assert_ne!(params.hash(), other.hash());
+}
+
+fn input(score: u64, threshold: u64) -> ZkVmInput {
+    ZkVmInput {
+        score: ScoreInput::Plain(score),
+        threshold,
+        old_state_root: [9; 32],
+        nonce: 7,
+        blinding: [42; 32],
+    }
+}
+
+#[test]
+fn guest_journal_binds_input() {
+    let commitment = score_commitment(5, &[42; 32]);
+
+    assert_eq!(
+        input(5, 10).journal(),
+        ZkVmJournal {
+            threshold: 10,
+            old_state_root: [9; 32],
+            nonce: 7,
+            payload_commitment: commitment,
+            new_state_root: next_state_root(&[9; 32], 7, &commitment),
+            model_hash: NO_MODEL,
+            dataset_root: NO_DATASET,
+            output: ZkVmOutput { ok: true },
+        }
+    );
+}
+
+#[test]
+fn guest_journal_reports_policy_violation() {
+    assert!(input(10, 10).journal().output.ok);
+    assert!(!input(11, 10).journal().output.ok);
+}
+
+#[test]
+fn dataset_path_follows_index_bits() {
+    let leaf = record_leaf(1, 5, &[2; 32]);
+    let siblings = core::array::from_fn(|level| [level as u8; 32]);
+
+    // index 0b10: left child at level 0, right child at level 1
+    let path = DatasetPath { siblings, index: 0b10 };
+    let level1 = merkle_node(&leaf, &siblings[0]);
+    let level2 = merkle_node(&siblings[1], &level1);
+    let expected = siblings[2..]
+        .iter()
+        .fold(level2, |node, sibling| merkle_node(&node, sibling));
+
+    assert_eq!(path.root(&leaf), expected);
+    assert_ne!(DatasetPath { siblings, index: 0 }.root(&leaf), expected);
+
+    let record = DatasetRecord { id: 1, salt: [2; 32], path };
+    assert_eq!(record.root(5), expected);
+}
+
+#[test]
+fn record_leaf_depends_on_id_and_salt() {
+    let leaf = record_leaf(1, 5, &[2; 32]);
+
+    assert_ne!(leaf, record_leaf(3, 5, &[2; 32]));
+    assert_ne!(leaf, record_leaf(1, 5, &[4; 32]));
+    assert_ne!(leaf, record_leaf(1, 6, &[2; 32]));
+}
+
+const CHAIN_IMAGE: [u32; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+/// Link applying `score` on top of `previous` (genesis when `None`)
+fn link(previous: Option<&ChainJournal>, score: u64) -> ChainInput {
+    let (old_state_root, nonce) = previous.map_or(([0; 32], 1), |p| (p.state_root, p.nonce + 1));
+
+    ChainInput {
+        image_id: CHAIN_IMAGE,
+        previous: previous.cloned(),
+        transition: ZkVmInput {
+            old_state_root,
+            nonce,
+            ..input(score, 10)
+        },
+    }
+}
+
+#[test]
+fn chain_journal_round_trips() {
+    let journal = link(None, 5).journal();
+
+    assert_eq!(ChainJournal::decode(&journal.encode()), Some(journal));
+}
+
+#[test]
+fn chain_links_follow_state_transitions() {
+    let first = link(None, 5).journal();
+    let second = link(Some(&first), 3).journal();
+
+    let root1 = next_state_root(&[0; 32], 1, &score_commitment(5, &[42; 32]));
+    let root2 = next_state_root(&root1, 2, &score_commitment(3, &[42; 32]));
+
+    assert!(first.output.ok && second.output.ok);
+    assert_eq!((second.state_root, second.nonce), (root2, 2));
+}
+
+#[test]
+fn chain_rejects_broken_links() {
+    let first = link(None, 5).journal();
+
+    // skipped nonce
+    let mut gap = link(Some(&first), 3);
+    gap.transition.nonce += 1;
+    assert!(!gap.journal().output.ok);
+
+    // previous link from another program
+    let mut foreign = link(Some(&first), 3);
+    foreign.image_id[0] ^= 1;
+    assert!(!foreign.journal().output.ok);
+
+    // looser threshold than the chain's
+    let mut looser = link(Some(&first), 3);
+    looser.transition.threshold += 1;
+    assert!(!looser.journal().output.ok);
+
+    // previous link was itself rejected
+    let rejected = link(None, 11).journal();
+    assert!(!rejected.output.ok);
+    assert!(!link(Some(&rejected), 3).journal().output.ok);
+}
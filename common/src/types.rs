@@ -1,14 +1,333 @@
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::state::{next_state_root, ProtocolState};
 
 pub type Hash = [u8; 32];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Commitment(pub Hash);
 
+/// Features read by the zkVM scoring model
+pub const MODEL_FEATURES: usize = 8;
+
+/// Model hash committed when the host supplies the score directly
+pub const NO_MODEL: Hash = [0u8; 32];
+
+/// Dataset root committed by guests that do not check membership
+pub const NO_DATASET: Hash = [0u8; 32];
+
+/// Fixed-point weighted scoring model
+///
+/// score = sum(features[i] * weights[i]), computed at the product scale
+/// of the two (e.g. Q16.16 features with Q8.8 weights give a Q24.24
+/// score), as in the Halo2 `WeightedScoreCircuit`. The bounds on both
+/// keep the sum below 2^51, so it never overflows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelParams {
+    pub weights: [u16; MODEL_FEATURES],
+}
+
+impl ModelParams {
+    pub fn score(&self, features: &[u32; MODEL_FEATURES]) -> u64 {
+        features
+            .iter()
+            .zip(self.weights.iter())
+            .map(|(f, w)| *f as u64 * *w as u64)
+            .sum()
+    }
+
+    /// Hash committed in the journal, identifying the model that was run
+    pub fn hash(&self) -> Hash {
+        let mut hasher = Sha256::new();
+        hasher.update(b"zkcg/model-params/v1");
+        for weight in self.weights {
+            hasher.update(weight.to_le_bytes());
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// Where the guest takes the score from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ScoreInput {
+    /// Score computed by the host
+    Plain(u64),
+    /// Score computed in the guest from private features
+    Model {
+        features: [u32; MODEL_FEATURES],
+        params: ModelParams,
+    },
+}
+
+impl ScoreInput {
+    pub fn score(&self) -> u64 {
+        match self {
+            Self::Plain(score) => *score,
+            Self::Model { features, params } => params.score(features),
+        }
+    }
+
+    /// Payload commitment: `score_commitment` or `features_commitment`
+    pub fn commitment(&self, blinding: &[u8; 32]) -> Hash {
+        match self {
+            Self::Plain(score) => score_commitment(*score, blinding),
+            Self::Model { features, .. } => features_commitment(features, blinding),
+        }
+    }
+
+    pub fn model_hash(&self) -> Hash {
+        match self {
+            Self::Plain(_) => NO_MODEL,
+            Self::Model { params, .. } => params.hash(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ZkVmInput {
-    pub score: u64,
+    pub score: ScoreInput,
     pub threshold: u64,
     pub old_state_root: [u8; 32],
     pub nonce: u64,
+    pub blinding: [u8; 32],
+}
+
+impl ZkVmInput {
+    /// Journal every guest commits for this input
+    ///
+    /// Evaluates the score, the threshold rule and the state transition.
+    /// The policy verdict goes in `output.ok` rather than a panic, so the
+    /// host can tell a rejected submission from an executor failure.
+    /// Guests that check more than the score fill in the extra fields.
+    pub fn journal(&self) -> ZkVmJournal {
+        let payload_commitment = self.score.commitment(&self.blinding);
+
+        ZkVmJournal {
+            threshold: self.threshold,
+            old_state_root: self.old_state_root,
+            nonce: self.nonce,
+            payload_commitment,
+            new_state_root: next_state_root(&self.old_state_root, self.nonce, &payload_commitment),
+            model_hash: self.score.model_hash(),
+            dataset_root: NO_DATASET,
+            output: ZkVmOutput {
+                ok: self.score.score() <= self.threshold,
+            },
+        }
+    }
+}
+
+/// zkVM payload commitment: H(score || blinding)
+pub fn score_commitment(score: u64, blinding: &[u8; 32]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(b"zkcg/score-commitment/v1");
+    hasher.update(score.to_le_bytes());
+    hasher.update(blinding);
+    hasher.finalize().into()
+}
+
+/// zkVM payload commitment for model inputs: H(features || blinding)
+pub fn features_commitment(features: &[u32; MODEL_FEATURES], blinding: &[u8; 32]) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(b"zkcg/features-commitment/v1");
+    for feature in features {
+        hasher.update(feature.to_le_bytes());
+    }
+    hasher.update(blinding);
+    hasher.finalize().into()
+}
+
+/// Verdict the zkVM guest reaches on the submission
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZkVmOutput {
+    pub ok: bool,
+}
+
+/// Public journal committed by the zkVM guest
+///
+/// The guest commits `encode()` as a single slice and the verifier reads
+/// it back with `decode`, so the field order lives here only:
+/// threshold, old_state_root, nonce, payload_commitment, new_state_root,
+/// model_hash, dataset_root, ok. Integers are little-endian; `ok` is one
+/// 0/1 byte. `model_hash` is `ModelParams::hash` of the model the guest
+/// ran, or `NO_MODEL` for a host-supplied score. `dataset_root` is the
+/// root the record was proven against, or `NO_DATASET`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZkVmJournal {
+    pub threshold: u64,
+    pub old_state_root: Hash,
+    pub nonce: u64,
+    pub payload_commitment: Hash,
+    pub new_state_root: Hash,
+    pub model_hash: Hash,
+    pub dataset_root: Hash,
+    pub output: ZkVmOutput,
+}
+
+impl ZkVmJournal {
+    pub const ENCODED_LEN: usize = 8 + 32 + 8 + 32 + 32 + 32 + 32 + 1;
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let fields: [&[u8]; 8] = [
+            &self.threshold.to_le_bytes(),
+            &self.old_state_root,
+            &self.nonce.to_le_bytes(),
+            &self.payload_commitment,
+            &self.new_state_root,
+            &self.model_hash,
+            &self.dataset_root,
+            &[self.output.ok as u8],
+        ];
+
+        let mut bytes = [0u8; Self::ENCODED_LEN];
+        let mut offset = 0;
+        for field in fields {
+            bytes[offset..offset + field.len()].copy_from_slice(field);
+            offset += field.len();
+        }
+
+        bytes
+    }
+
+    /// Inverse of `encode`; `None` on a wrong length or a non-boolean `ok`
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::ENCODED_LEN {
+            return None;
+        }
+
+        let (threshold, rest) = bytes.split_at(8);
+        let (old_state_root, rest) = rest.split_at(32);
+        let (nonce, rest) = rest.split_at(8);
+        let (payload_commitment, rest) = rest.split_at(32);
+        let (new_state_root, rest) = rest.split_at(32);
+        let (model_hash, rest) = rest.split_at(32);
+        let (dataset_root, rest) = rest.split_at(32);
+
+        let ok = match rest {
+            [0] => false,
+            [1] => true,
+            _ => return None,
+        };
+
+        Some(Self {
+            threshold: u64::from_le_bytes(threshold.try_into().ok()?),
+            old_state_root: old_state_root.try_into().ok()?,
+            nonce: u64::from_le_bytes(nonce.try_into().ok()?),
+            payload_commitment: payload_commitment.try_into().ok()?,
+            new_state_root: new_state_root.try_into().ok()?,
+            model_hash: model_hash.try_into().ok()?,
+            dataset_root: dataset_root.try_into().ok()?,
+            output: ZkVmOutput { ok },
+        })
+    }
+}
+
+/// Journal of the zkVM chain guest: the state reached from genesis
+///
+/// `image_id` is the chain guest's own image ID (as words), which it
+/// cannot know when it is built. Each link takes it as input, verifies
+/// the previous link's receipt against it and commits it, so a verifier
+/// checks it once against the real ID. `threshold` is the one every link
+/// was checked against, so a verifier can hold the chain to its policy.
+/// Encoded as image_id, state_root, nonce, threshold, ok, with the same
+/// conventions as `ZkVmJournal`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainJournal {
+    pub image_id: [u32; 8],
+    pub state_root: Hash,
+    pub nonce: u64,
+    pub threshold: u64,
+    pub output: ZkVmOutput,
+}
+
+impl ChainJournal {
+    pub const ENCODED_LEN: usize = 32 + 32 + 8 + 8 + 1;
+
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut bytes = [0u8; Self::ENCODED_LEN];
+        for (chunk, word) in bytes[..32].chunks_exact_mut(4).zip(self.image_id) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes[32..64].copy_from_slice(&self.state_root);
+        bytes[64..72].copy_from_slice(&self.nonce.to_le_bytes());
+        bytes[72..80].copy_from_slice(&self.threshold.to_le_bytes());
+        bytes[80] = self.output.ok as u8;
+        bytes
+    }
+
+    /// Inverse of `encode`; `None` on a wrong length or a non-boolean `ok`
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::ENCODED_LEN {
+            return None;
+        }
+
+        let mut image_id = [0u32; 8];
+        for (word, chunk) in image_id.iter_mut().zip(bytes[..32].chunks_exact(4)) {
+            *word = u32::from_le_bytes(chunk.try_into().ok()?);
+        }
+
+        let ok = match bytes[80] {
+            0 => false,
+            1 => true,
+            _ => return None,
+        };
+
+        Some(Self {
+            image_id,
+            state_root: bytes[32..64].try_into().ok()?,
+            nonce: u64::from_le_bytes(bytes[64..72].try_into().ok()?),
+            threshold: u64::from_le_bytes(bytes[72..80].try_into().ok()?),
+            output: ZkVmOutput { ok },
+        })
+    }
+}
+
+/// Input of the zkVM chain guest: one transition on top of a chain
+#[derive(Serialize, Deserialize)]
+pub struct ChainInput {
+    pub image_id: [u32; 8],
+    /// Journal of the previous link, `None` for the first transition
+    /// after genesis. The guest verifies it as a receipt assumption.
+    pub previous: Option<ChainJournal>,
+    pub transition: ZkVmInput,
+}
+
+impl ChainInput {
+    /// Journal the chain guest commits for this link
+    ///
+    /// The link is ok only if the previous link was, it was produced by
+    /// the same image, and the transition starts from its state with the
+    /// next nonce, keeps its threshold and passes the score rule. The
+    /// first link sets the threshold. A rejected link keeps the previous
+    /// state.
+    pub fn journal(&self) -> ChainJournal {
+        let (state_root, nonce, threshold, linked) = match &self.previous {
+            None => {
+                let genesis = ProtocolState::genesis();
+                (genesis.state_root, genesis.nonce, self.transition.threshold, true)
+            }
+            Some(previous) => (
+                previous.state_root,
+                previous.nonce,
+                previous.threshold,
+                previous.output.ok && previous.image_id == self.image_id,
+            ),
+        };
+
+        let transition = self.transition.journal();
+        let ok = linked
+            && transition.output.ok
+            && transition.threshold == threshold
+            && transition.old_state_root == state_root
+            && nonce.checked_add(1) == Some(transition.nonce);
+
+        ChainJournal {
+            image_id: self.image_id,
+            state_root: if ok { transition.new_state_root } else { state_root },
+            nonce: if ok { transition.nonce } else { nonce },
+            threshold,
+            output: ZkVmOutput { ok },
+        }
+    }
 }
\ No newline at end of file
@@ -1,15 +1,25 @@
+use std::path::Path;
+
 use rand::rngs::OsRng;
 
 use halo2_proofs::{
-    circuit::Value,
-    plonk::{create_proof, keygen_pk, keygen_vk},
-    poly::commitment::Params,
+    plonk::{create_proof, keygen_pk},
     transcript::{Blake2bWrite, Challenge255},
 };
 
-use halo2curves::bn256::{Fr, G1Affine};
+use halo2curves::{
+    bn256::{Fr, G1Affine},
+    ff::Field,
+};
 
-use circuits::score_circuit::ScoreCircuit;
+use circuits::{
+    halo2_artifacts::Halo2Artifacts,
+    merkle::merkle_path,
+    score_circuit::{
+        commitment_to_bytes, public_instances, record_leaf, score_commitment, DatasetRecord,
+        ScoreCircuit, DATASET_DEPTH,
+    },
+};
 
 mod proof;
 use proof::Halo2Proof;
@@ -18,28 +28,60 @@ fn main() {
     // ---- real inputs
     let score: u64 = 42;
     let threshold: u64 = 40;
+    let old_state_root: [u8; 32] = [0u8; 32];
+    let nonce: u64 = 1;
+
+    // ---- fresh blinding keeps the score commitment hiding
+    let blinding = Fr::random(OsRng);
+    let commitment = score_commitment(score, blinding);
+
+    // ---- example dataset: record i scored i, each with its own salt;
+    //      ours sits at record_index
+    let record_index: u64 = 7;
+    let salts: Vec<Fr> = (0..1u64 << DATASET_DEPTH).map(|_| Fr::random(OsRng)).collect();
+    let leaves: Vec<Fr> = (0..1u64 << DATASET_DEPTH)
+        .map(|i| {
+            let record_score = if i == record_index { score } else { i };
+            record_leaf(Fr::from(i), record_score, salts[i as usize])
+        })
+        .collect();
+
+    let (dataset_root, siblings) = merkle_path(&leaves, record_index as usize);
+    let record = DatasetRecord {
+        id: Fr::from(record_index),
+        salt: salts[record_index as usize],
+        siblings: siblings.try_into().expect("path has DATASET_DEPTH siblings"),
+        index: record_index,
+    };
 
     // ---- circuit with witnesses
-    let circuit = ScoreCircuit::<Fr> {
-        score: Value::known(Fr::from(score)),
-        threshold: Value::known(Fr::from(threshold)),
+    let circuit = ScoreCircuit::<Fr>::new(score, threshold, old_state_root, nonce, blinding, &record);
+
+    // ---- params + vk, shared with the verifier node via an artifact file
+    let artifacts = match std::env::args().nth(1) {
+        Some(path) if Path::new(&path).exists() => {
+            Halo2Artifacts::read_from(&path).expect("artifact file rejected")
+        }
+        Some(path) => {
+            let artifacts = Halo2Artifacts::for_score_circuit();
+            artifacts
+                .write_to(&path)
+                .expect("failed to write artifact file");
+            artifacts
+        }
+        None => Halo2Artifacts::for_score_circuit(),
     };
+    println!("Artifacts fingerprint: {}", hex(&artifacts.fingerprint()));
 
-    // ---- security parameter
-    let k: u32 = 9;
-
-    // ---- KZG params (commitment curve = G1Affine)
-    let params: Params<G1Affine> = Params::new(k);
+    let Halo2Artifacts { params, vk } = artifacts;
 
     // ---- key generation
-    let vk = keygen_vk(&params, &circuit)
-        .expect("vk generation failed");
-
     let pk = keygen_pk(&params, vk, &circuit)
         .expect("pk generation failed");
 
     // ---- public inputs (instance column)
-    let public_inputs: Vec<Vec<Fr>> = vec![vec![Fr::from(threshold)]];
+    let public_inputs: Vec<Vec<Fr>> =
+        vec![public_instances(threshold, &old_state_root, nonce, commitment, dataset_root)];
     let instance_slices: Vec<&[Fr]> = public_inputs.iter().map(|v| v.as_slice()).collect();
     let all_instances: Vec<&[&[Fr]]> = vec![instance_slices.as_slice()];
 
@@ -65,4 +107,10 @@ fn main() {
         bincode::serialize(&proof).expect("proof serialization failed");
 
     println!("Halo2 proof generated ({} bytes)", encoded.len());
+    println!("Payload commitment: {}", hex(&commitment_to_bytes(&commitment)));
+    println!("Dataset root: {}", hex(&commitment_to_bytes(&dataset_root)));
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
@@ -1,18 +1,24 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use zkcg_common::state::ProtocolState;
+use zkcg_common::state::{next_state_root, ProtocolState};
+use zkcg_common::types::Commitment;
+use zkcg_verifier::backend::PolicyKind;
 use zkcg_verifier::engine::{VerifierEngine, PublicInputs};
+#[cfg(feature = "zk-vm")]
 use zkcg_verifier::backend_zkvm::ZkVmBackend;  // real one
-use zkcg_verifier::backend_halo2::Halo2Backend; // we'll bypass the heavy part
 
 // Temporary bench-only backend
 struct BenchHalo2Backend;
 
 impl zkcg_verifier::backend::ProofBackend for BenchHalo2Backend {
-    fn verify(&self, _proof: &[u8], _inputs: &PublicInputs) -> Result<(), zkcg_common::errors::ProtocolError> {
-        // Simulate Halo2 verification cost (pairing, FFT, etc.)
+    fn name(&self) -> &'static str {
+        "halo2-bench"
+    }
+
+    fn verify(&self, _proof: &[u8], _inputs: &PublicInputs) -> Result<PolicyKind, zkcg_common::errors::ProtocolError> {
+        // Simulate Halo2 verification cost (IPA multi-scalar multiplication, etc.)
         // Or leave empty for pure engine overhead
-        // For real cost, you'd need to run actual verify_proof with small circuit
-        Ok(())
+        // For real cost, see bench_halo2_layouts
+        Ok(PolicyKind::Membership)
     }
 }
 
@@ -27,9 +33,12 @@ fn bench_verification(c: &mut Criterion) {
         threshold: 600,
         old_state_root: [0; 32],
         nonce: 1,
+        payload_commitment: [0; 32],
+        dataset_root: [0; 32],
     };
 
     let state = ProtocolState::genesis();
+    let next_root = next_state_root(&inputs.old_state_root, inputs.nonce, &inputs.payload_commitment);
 
     // Halo2 path — using dummy backend to isolate engine
     {
@@ -38,24 +47,145 @@ fn bench_verification(c: &mut Criterion) {
         let proof = mock_proof();
 
         group.bench_function("halo2 (simulated)", |b| {
-            b.iter(|| engine.process_transition(&proof, inputs, zkcg_common::types::Commitment([0; 32])))
+            b.iter(|| engine.process_transition(&proof, inputs, Commitment(next_root)))
         });
     }
 
     // zkVM path — real backend, zero cost
     #[cfg(feature = "zk-vm")]
     {
-        let backend = ZkVmBackend;
+        let backend = ZkVmBackend::new();
         let mut engine = VerifierEngine::new(state, Box::new(backend));
         let proof = mock_proof();
 
         group.bench_function("zkvm (real)", |b| {
-            b.iter(|| engine.process_transition(&proof, inputs, zkcg_common::types::Commitment([0; 32])))
+            b.iter(|| engine.process_transition(&proof, inputs, Commitment(next_root)))
         });
     }
 
     group.finish();
 }
 
+/* ---------------- Halo2 range-check layouts ---------------- */
+
+#[cfg(feature = "zk-halo2")]
+mod halo2_layouts {
+    use criterion::Criterion;
+    use rand::rngs::OsRng;
+
+    use halo2_proofs::{
+        plonk::{create_proof, keygen_pk, keygen_vk, Circuit, ProvingKey},
+        poly::commitment::Params,
+        transcript::{Blake2bWrite, Challenge255},
+    };
+    use halo2curves::bn256::{Fr, G1Affine};
+
+    use circuits::{
+        halo2_artifacts::SCORE_CIRCUIT_K,
+        score_circuit::{
+            commitment_from_bytes, commitment_to_bytes, public_instances, score_commitment,
+            DatasetRecord, LookupScoreCircuit, ScoreCircuit, DATASET_DEPTH,
+        },
+    };
+    use zkcg_verifier::{backend::ProofBackend, engine::PublicInputs, Halo2Backend};
+
+    const K: u32 = SCORE_CIRCUIT_K;
+
+    fn prove<C: Circuit<Fr>>(
+        params: &Params<G1Affine>,
+        pk: &ProvingKey<G1Affine>,
+        circuit: C,
+        inputs: &PublicInputs,
+    ) -> Vec<u8> {
+        let instances = [public_instances::<Fr>(
+            inputs.threshold,
+            &inputs.old_state_root,
+            inputs.nonce,
+            commitment_from_bytes(&inputs.payload_commitment).unwrap(),
+            commitment_from_bytes(&inputs.dataset_root).unwrap(),
+        )];
+        let instance_refs: Vec<&[Fr]> = instances.iter().map(|v| v.as_slice()).collect();
+
+        let mut transcript =
+            Blake2bWrite::<_, G1Affine, Challenge255<G1Affine>>::init(Vec::new());
+
+        create_proof(
+            params,
+            pk,
+            &[circuit],
+            &[instance_refs.as_slice()],
+            OsRng,
+            &mut transcript,
+        )
+        .unwrap();
+
+        transcript.finalize()
+    }
+
+    fn bench_layout<C: Circuit<Fr> + Clone>(
+        c: &mut Criterion,
+        name: &str,
+        circuit: C,
+        inputs: PublicInputs,
+    ) {
+        let params: Params<G1Affine> = Params::new(K);
+        let vk = keygen_vk(&params, &circuit).unwrap();
+        let pk = keygen_pk(&params, vk.clone(), &circuit).unwrap();
+
+        let proof = prove(&params, &pk, circuit.clone(), &inputs);
+        println!("{}: proof size {} bytes", name, proof.len());
+
+        let mut group = c.benchmark_group(format!("Halo2 layout ({})", name));
+        group.sample_size(10);
+
+        group.bench_function("prove", |b| {
+            b.iter(|| prove(&params, &pk, circuit.clone(), &inputs))
+        });
+
+        let backend = Halo2Backend { vk, params: params.clone() };
+        group.bench_function("verify", |b| {
+            b.iter(|| backend.verify(&proof, &inputs).unwrap())
+        });
+
+        group.finish();
+    }
+
+    pub fn bench_halo2_layouts(c: &mut Criterion) {
+        let blinding = Fr::from(0xB11D);
+        let record = DatasetRecord {
+            id: Fr::from(1),
+            salt: Fr::from(0x5A17),
+            siblings: [Fr::from(0); DATASET_DEPTH],
+            index: 0,
+        };
+        let inputs = PublicInputs {
+            threshold: 600,
+            old_state_root: [0; 32],
+            nonce: 1,
+            payload_commitment: commitment_to_bytes(&score_commitment::<Fr>(599, blinding)),
+            dataset_root: commitment_to_bytes(&record.root(599)),
+        };
+
+        bench_layout(
+            c,
+            "bit decomposition",
+            ScoreCircuit::<Fr>::new(599, inputs.threshold, inputs.old_state_root, inputs.nonce, blinding, &record),
+            inputs,
+        );
+
+        bench_layout(
+            c,
+            "lookup",
+            LookupScoreCircuit::<Fr>::new(599, inputs.threshold, inputs.old_state_root, inputs.nonce, blinding, &record),
+            inputs,
+        );
+    }
+}
+
+#[cfg(feature = "zk-halo2")]
+criterion_group!(benches, bench_verification, halo2_layouts::bench_halo2_layouts);
+
+#[cfg(not(feature = "zk-halo2"))]
 criterion_group!(benches, bench_verification);
-criterion_main!(benches);
\ No newline at end of file
+
+criterion_main!(benches);
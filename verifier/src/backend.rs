@@ -1,10 +1,49 @@
-use zkcg_common::errors::ProtocolError;
+use zkcg_common::{
+    errors::ProtocolError,
+    state,
+    types::Hash,
+};
 use crate::engine::PublicInputs;
 
+/// Rule an accepted proof attests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyKind {
+    /// score <= threshold
+    Score,
+    /// score <= threshold, for a record in the dataset at `dataset_root`
+    Membership,
+}
+
+impl PolicyKind {
+    /// Short name used in receipts and logs
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Score => "score",
+            Self::Membership => "membership",
+        }
+    }
+}
+
 pub trait ProofBackend: Send + Sync {
+    /// Short name identifying the backend in receipts and logs
+    fn name(&self) -> &'static str;
+
+    /// Verify a proof against `public_inputs` and report the rule it attests
     fn verify(
         &self,
         proof_bytes: &[u8],
         public_inputs: &PublicInputs,
-    ) -> Result<(), ProtocolError>;
+    ) -> Result<PolicyKind, ProtocolError>;
+
+    /// State root a proof accepted by `verify` transitions to
+    ///
+    /// Must match the transition the backend's proofs compute in-proof.
+    /// Defaults to `zkcg_common::state::next_state_root`.
+    fn next_state_root(&self, public_inputs: &PublicInputs) -> Result<Hash, ProtocolError> {
+        Ok(state::next_state_root(
+            &public_inputs.old_state_root,
+            public_inputs.nonce,
+            &public_inputs.payload_commitment,
+        ))
+    }
 }
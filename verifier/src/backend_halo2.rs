@@ -1,35 +1,123 @@
 #![cfg(feature = "zk-halo2")]
 
-use zkcg_common::errors::ProtocolError;
+use zkcg_common::{errors::ProtocolError, types::Hash};
 use crate::{
-    backend::ProofBackend,
+    backend::{PolicyKind, ProofBackend},
     engine::PublicInputs,
 };
 
 use halo2_proofs::{
-    plonk::{verify_proof, VerifyingKey, SingleVerifier},
+    plonk::{verify_proof, BatchVerifier, VerifyingKey, SingleVerifier},
     poly::commitment::Params,
     transcript::{Blake2bRead, Challenge255},
 };
 
 use halo2curves::bn256::{Fr, G1Affine};
 
-/// Real Halo2 verifier backend (runtime keys, KZG implicit)
+use std::path::Path;
+
+use circuits::{
+    halo2_artifacts::{Halo2Artifacts, Halo2ArtifactsError},
+    score_circuit::{commitment_from_bytes, commitment_to_bytes, public_instances, transition_root},
+};
+
+/// Real Halo2 verifier backend (runtime keys)
+///
+/// Commitments use the transparent IPA scheme over bn256 G1: no trusted
+/// setup, but verification is linear in the circuit size and proofs are
+/// larger than KZG ones. halo2_proofs 0.3 implements IPA only; a KZG
+/// backend would need a halo2 fork that provides it.
 pub struct Halo2Backend {
     pub vk: VerifyingKey<G1Affine>,
     pub params: Params<G1Affine>,
 }
 
+impl Halo2Backend {
+    /// Backend over artifacts shared with the prover
+    /// (see `Halo2Artifacts::read_from`)
+    pub fn from_artifacts(artifacts: Halo2Artifacts) -> Self {
+        Self {
+            vk: artifacts.vk,
+            params: artifacts.params,
+        }
+    }
+
+    /// Backend over the artifact file the prover wrote
+    /// (see `Halo2Artifacts::write_to`)
+    pub fn read_from(path: impl AsRef<Path>) -> Result<Self, Halo2ArtifactsError> {
+        Halo2Artifacts::read_from(path).map(Self::from_artifacts)
+    }
+
+    /// Verify a burst of proofs with one amortised multi-scalar
+    /// multiplication (`BatchVerifier`)
+    ///
+    /// Returns one result per submission, in order. If the batch check
+    /// fails, every proof is re-verified on its own so the failing ones
+    /// can be identified.
+    pub fn verify_batch(
+        &self,
+        batch: &[(&[u8], PublicInputs)],
+    ) -> Vec<Result<PolicyKind, ProtocolError>> {
+        let instances: Result<Vec<_>, _> = batch
+            .iter()
+            .map(|(_, public_inputs)| instances(public_inputs))
+            .collect();
+
+        if let Ok(instances) = instances {
+            let mut verifier = BatchVerifier::new();
+
+            for ((proof_bytes, _), instances) in batch.iter().zip(instances) {
+                verifier.add_proof(vec![vec![instances]], proof_bytes.to_vec());
+            }
+
+            if verifier.finalize(&self.params, &self.vk) {
+                return batch.iter().map(|_| Ok(PolicyKind::Membership)).collect();
+            }
+        }
+
+        batch
+            .iter()
+            .map(|(proof_bytes, public_inputs)| self.verify(proof_bytes, public_inputs))
+            .collect()
+    }
+}
+
+fn payload_commitment(public_inputs: &PublicInputs) -> Result<Fr, ProtocolError> {
+    field_element(&public_inputs.payload_commitment)
+}
+
+fn field_element(bytes: &[u8; 32]) -> Result<Fr, ProtocolError> {
+    commitment_from_bytes::<Fr>(bytes).ok_or(ProtocolError::InvalidProof)
+}
+
+/// Instance column for `public_inputs`
+///
+/// A payload commitment or dataset root that is not a canonical field
+/// element cannot have been exposed by any proof.
+fn instances(public_inputs: &PublicInputs) -> Result<Vec<Fr>, ProtocolError> {
+    let commitment = payload_commitment(public_inputs)?;
+
+    Ok(public_instances::<Fr>(
+        public_inputs.threshold,
+        &public_inputs.old_state_root,
+        public_inputs.nonce,
+        commitment,
+        field_element(&public_inputs.dataset_root)?,
+    ))
+}
+
 impl ProofBackend for Halo2Backend {
+    fn name(&self) -> &'static str {
+        "halo2"
+    }
+
     fn verify(
         &self,
         proof_bytes: &[u8],
         public_inputs: &PublicInputs,
-    ) -> Result<(), ProtocolError> {
+    ) -> Result<PolicyKind, ProtocolError> {
         // --- public inputs (instance columns)
-        let threshold = Fr::from(public_inputs.threshold as u64);
-
-        let instance_values = vec![vec![threshold]];
+        let instance_values = [instances(public_inputs)?];
         let instance_slices: Vec<&[Fr]> =
             instance_values.iter().map(|v| v.as_slice()).collect();
         let all_instances: Vec<&[&[Fr]]> =
@@ -57,7 +145,19 @@ impl ProofBackend for Halo2Backend {
         )
         .map_err(|_| ProtocolError::InvalidProof)?;
 
-        Ok(())
+        // the score circuit always proves membership in `dataset_root`
+        Ok(PolicyKind::Membership)
+    }
+
+    /// Poseidon transition the circuit exposes at `NEW_STATE_ROOT_ROW`
+    fn next_state_root(&self, public_inputs: &PublicInputs) -> Result<Hash, ProtocolError> {
+        let new_state_root = transition_root::<Fr>(
+            &public_inputs.old_state_root,
+            public_inputs.nonce,
+            payload_commitment(public_inputs)?,
+        );
+
+        Ok(commitment_to_bytes(&new_state_root))
     }
 }
 
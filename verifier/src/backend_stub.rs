@@ -1,6 +1,6 @@
 use zkcg_common::errors::ProtocolError;
 use crate::{
-    backend::ProofBackend,
+    backend::{PolicyKind, ProofBackend},
     engine::PublicInputs,
 };
 
@@ -8,12 +8,16 @@ use crate::{
 pub struct StubBackend;
 
 impl ProofBackend for StubBackend {
+    fn name(&self) -> &'static str {
+        "stub"
+    }
+
     fn verify(
         &self,
         _proof_bytes: &[u8],
         _public_inputs: &PublicInputs,
-    ) -> Result<(), ProtocolError> {
-        // Rust-only mode: always accept
-        Ok(())
+    ) -> Result<PolicyKind, ProtocolError> {
+        // Rust-only mode: always accept, but vouch for no dataset
+        Ok(PolicyKind::Score)
     }
 }
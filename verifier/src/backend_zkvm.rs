@@ -1,57 +1,209 @@
 #![cfg(feature = "zk-vm")]
 
 use zkcg_common::errors::ProtocolError;
-use crate::{backend::ProofBackend, engine::PublicInputs};
+use crate::{
+    backend::{PolicyKind, ProofBackend},
+    engine::PublicInputs,
+    policy::PolicySet,
+};
 
-use risc0_zkp::core::digest::Digest;
-use serde::Deserialize;
+use std::collections::HashMap;
 
-use serde::Serialize;
-use zkcg_zkvm_host::method_id;
+use risc0_zkvm::{Digest, InnerReceipt, VerifierContext};
+use zkcg_common::{
+    state::ProtocolState,
+    types::{ChainJournal, Hash, ZkVmJournal, NO_DATASET},
+};
+use zkcg_zkvm_host::{GuestProgram, ReceiptKind, ZkVmProof};
 use bincode;
 
-#[derive(Deserialize, Debug)]
-struct ZkVmProof {
-    method_id: Digest,
-    journal_digest: Digest,
+/// Verifies RISC Zero receipts for the ZKCG guests
+///
+/// Each accepted image ID is registered with the policy kind its guest
+/// enforces; receipts of any other program are rejected.
+///
+/// Fake (dev-mode) receipts are rejected unless dev mode is enabled on
+/// the backend itself; the `RISC0_DEV_MODE` environment variable has no
+/// effect here.
+#[derive(Debug)]
+pub struct ZkVmBackend {
+    dev_mode: bool,
+    model: Option<Hash>,
+    programs: HashMap<Digest, PolicyKind>,
 }
 
-#[derive(serde::Serialize)]
-struct ZkVmOutput {
-    pub ok: bool,
+impl Default for ZkVmBackend {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct ZkVmJournal {
-    pub threshold: u64,
-    pub old_state_root: [u8; 32],
-    pub nonce: u64,
-    pub ok: bool,
-}
+impl ZkVmBackend {
+    /// Production backend: real receipts only, for every guest built
+    /// into `zkcg_zkvm_host`
+    pub fn new() -> Self {
+        let programs = GuestProgram::ALL
+            .into_iter()
+            .filter_map(|program| {
+                let kind = match program {
+                    GuestProgram::Score => PolicyKind::Score,
+                    GuestProgram::Membership => PolicyKind::Membership,
+                    // attests history, not a transition; see `verify_chain`
+                    GuestProgram::Chain => return None,
+                };
+                Some((program.method_id(), kind))
+            })
+            .collect();
 
-pub struct ZkVmBackend;
+        Self {
+            dev_mode: false,
+            model: None,
+            programs,
+        }
+    }
 
-impl ProofBackend for ZkVmBackend {
-    fn verify(
+    /// Accept receipts of another guest program, enforcing `kind`
+    pub fn with_program(mut self, image_id: Digest, kind: PolicyKind) -> Self {
+        self.programs.insert(image_id, kind);
+        self
+    }
+
+    /// Policy kind registered for `image_id`, if any
+    pub fn policy_kind(&self, image_id: &Digest) -> Option<PolicyKind> {
+        self.programs.get(image_id).copied()
+    }
+
+    /// Accept fake receipts from `zkcg_zkvm_host::prove_dev`
+    ///
+    /// For tests and local development only.
+    pub fn with_dev_mode(mut self, enabled: bool) -> Self {
+        self.dev_mode = enabled;
+        self
+    }
+
+    /// Only accept proofs whose guest ran the model with this
+    /// `ModelParams::hash`
+    pub fn with_model(mut self, model_hash: Hash) -> Self {
+        self.model = Some(model_hash);
+        self
+    }
+
+    /// Verify a proof and report which rule its guest enforced
+    pub fn verify_policy(
         &self,
         proof_bytes: &[u8],
-        _public_inputs: &PublicInputs,
-    ) -> Result<(), ProtocolError> {
+        public_inputs: &PublicInputs,
+    ) -> Result<PolicyKind, ProtocolError> {
         // 1️⃣ Deserialize opaque proof
         let proof: ZkVmProof =
             bincode::deserialize(proof_bytes)
                 .map_err(|_| ProtocolError::InvalidProof)?;
-        println!("Verifying zkVM proof: {:?}", proof);
-        // 2️⃣ Verify method identity
-        if proof.method_id != method_id() {
+        // 2️⃣ Verify method identity against the registry
+        let kind = self
+            .policy_kind(&proof.method_id)
+            .ok_or(ProtocolError::InvalidProof)?;
+
+        // 3️⃣ Verify the receipt: seal, claim and journal, against its image ID
+        self.verify_receipt(&proof)?;
+
+        // 4️⃣ Bind the committed journal to the submitted public inputs
+        let journal = ZkVmJournal::decode(&proof.receipt.journal.bytes)
+            .ok_or(ProtocolError::InvalidProof)?;
+
+        if journal.threshold != public_inputs.threshold
+            || journal.old_state_root != public_inputs.old_state_root
+            || journal.nonce != public_inputs.nonce
+            || journal.payload_commitment != public_inputs.payload_commitment
+            || journal.new_state_root != self.next_state_root(public_inputs)?
+            || journal.dataset_root != public_inputs.dataset_root
+            || !journal.output.ok
+        {
             return Err(ProtocolError::InvalidProof);
         }
-        // NOTE:
-        // Journal digest is produced by zkVM runtime.
-        // Verifier must NOT recompute it from raw values.
-        // State binding is enforced inside the guest.
 
+        if self.model.is_some_and(|model| journal.model_hash != model) {
+            return Err(ProtocolError::InvalidProof);
+        }
 
-        Ok(())
+        Ok(kind)
+    }
+
+    /// Verify that a proof from `zkcg_zkvm_host::prove_chain` attests `state`
+    ///
+    /// One verification covers every transition from genesis to `state`.
+    /// The threshold every link was checked against must satisfy
+    /// `policies`, usually those of the epoch in force at `state`.
+    pub fn verify_chain(
+        &self,
+        proof_bytes: &[u8],
+        state: &ProtocolState,
+        policies: &PolicySet,
+    ) -> Result<(), ProtocolError> {
+        let proof: ZkVmProof =
+            bincode::deserialize(proof_bytes)
+                .map_err(|_| ProtocolError::InvalidProof)?;
+
+        let chain_id = GuestProgram::Chain.method_id();
+        if proof.method_id != chain_id {
+            return Err(ProtocolError::InvalidProof);
+        }
+
+        self.verify_receipt(&proof)?;
+
+        let journal = ChainJournal::decode(&proof.receipt.journal.bytes)
+            .ok_or(ProtocolError::InvalidProof)?;
+
+        // every link verified its predecessor against `image_id`
+        if Digest::from(journal.image_id) != chain_id
+            || journal.state_root != state.state_root
+            || journal.nonce != state.nonce
+            || !journal.output.ok
+        {
+            return Err(ProtocolError::InvalidProof);
+        }
+
+        // a chain attests scores only, never dataset membership
+        let inputs = PublicInputs {
+            threshold: journal.threshold,
+            old_state_root: state.state_root,
+            nonce: state.nonce,
+            payload_commitment: [0u8; 32],
+            dataset_root: NO_DATASET,
+        };
+        policies.enforce(&inputs, PolicyKind::Score)
+    }
+
+    fn verify_receipt(&self, proof: &ZkVmProof) -> Result<(), ProtocolError> {
+        // The recorded kind must describe the receipt; fake receipts are
+        // left to the dev-mode check below
+        let kind_matches = matches!(
+            (&proof.receipt.inner, proof.receipt_kind),
+            (InnerReceipt::Composite(_), ReceiptKind::Composite)
+                | (InnerReceipt::Succinct(_), ReceiptKind::Succinct)
+                | (InnerReceipt::Fake(_), _)
+        );
+        if !kind_matches {
+            return Err(ProtocolError::InvalidProof);
+        }
+
+        let ctx = VerifierContext::default().with_dev_mode(self.dev_mode);
+        proof
+            .receipt
+            .verify_with_context(&ctx, proof.method_id)
+            .map_err(|_| ProtocolError::InvalidProof)
+    }
+}
+
+impl ProofBackend for ZkVmBackend {
+    fn name(&self) -> &'static str {
+        "zkvm"
+    }
+
+    fn verify(
+        &self,
+        proof_bytes: &[u8],
+        public_inputs: &PublicInputs,
+    ) -> Result<PolicyKind, ProtocolError> {
+        self.verify_policy(proof_bytes, public_inputs)
     }
 }
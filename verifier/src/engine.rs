@@ -1,35 +1,116 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
 use zkcg_common::{
     errors::ProtocolError,
     state::ProtocolState,
-    types::Commitment,
+    types::{Commitment, Hash},
 };
-use crate::backend::ProofBackend;
-use crate::policy;
+use crate::backend::{PolicyKind, ProofBackend};
+use crate::epoch::{Epoch, EpochSchedule, ScheduleError};
+use crate::storage::{MemoryStateStore, StateStore, StorageError, StoredState};
 
 pub struct VerifierEngine {
     state: ProtocolState,
-    backend: Box<dyn ProofBackend>,
+    schedule: EpochSchedule,
+    store: Box<dyn StateStore>,
 }
 
 impl VerifierEngine {
+    /// Engine verifying every transition with `backend` and the default policies
+    ///
+    /// State is kept in a `MemoryStateStore`.
     pub fn new(
         state: ProtocolState,
         backend: Box<dyn ProofBackend>,
     ) -> Self {
-        Self { state, backend }
+        let schedule = EpochSchedule::new().with_epoch(state.epoch, Epoch::new(backend, "default"));
+        let store = Box::new(MemoryStateStore::new(state.clone()));
+        Self { state, schedule, store }
+    }
+
+    /// Engine routing transitions through `schedule`
+    ///
+    /// The schedule must register the state's current epoch.
+    pub fn with_schedule(
+        state: ProtocolState,
+        schedule: EpochSchedule,
+    ) -> Result<Self, ScheduleError> {
+        if schedule.get(state.epoch).is_none() {
+            return Err(ScheduleError::UnknownEpoch(state.epoch));
+        }
+        let store = Box::new(MemoryStateStore::new(state.clone()));
+        Ok(Self { state, schedule, store })
+    }
+
+    /// Engine resuming from the state held by `store` and committing
+    /// every accepted transition and epoch bump to it
+    ///
+    /// Bumps scheduled before the restart are restored into `schedule`,
+    /// which must register their epochs as well as the current one.
+    pub fn open(
+        store: Box<dyn StateStore>,
+        mut schedule: EpochSchedule,
+    ) -> Result<Self, OpenError> {
+        let StoredState { state, activations } = store.load()?;
+        if schedule.get(state.epoch).is_none() {
+            return Err(ScheduleError::UnknownEpoch(state.epoch).into());
+        }
+        for (at_nonce, epoch) in activations {
+            schedule.schedule(epoch, at_nonce)?;
+        }
+        Ok(Self { state, schedule, store })
     }
     
     pub fn state(&self) -> &ProtocolState {
         &self.state
     }
 
+    /// Epoch the current state is verified under
+    pub fn epoch(&self) -> &Epoch {
+        self.schedule
+            .get(self.state.epoch)
+            .expect("current epoch is registered")
+    }
+
+    /// Register `epoch` under `id`; registered epochs, the current one
+    /// included, cannot be replaced
+    pub fn register_epoch(&mut self, id: u64, epoch: Epoch) -> Result<(), ScheduleError> {
+        self.schedule.insert(id, epoch)
+    }
+
+    /// Switch to epoch `epoch` for the transition carrying `at_nonce`
+    /// and all later ones
+    ///
+    /// Both must lie ahead of the current state. The bump is committed
+    /// to the store, so it survives a restart.
+    pub fn schedule_epoch(&mut self, epoch: u64, at_nonce: u64) -> Result<(), ScheduleError> {
+        if epoch <= self.state.epoch || at_nonce <= self.state.nonce {
+            return Err(ScheduleError::OutOfOrder { epoch, at_nonce });
+        }
+        self.schedule.schedule(epoch, at_nonce)?;
+
+        if self.store.save(&self.stored()).is_err() {
+            self.schedule.unschedule(at_nonce);
+            return Err(ScheduleError::StorageFailure);
+        }
+        Ok(())
+    }
+
+    fn stored(&self) -> StoredState {
+        StoredState {
+            state: self.state.clone(),
+            activations: self.schedule.activations().clone(),
+        }
+    }
 
     pub fn process_transition(
         &mut self,
         proof_bytes: &[u8],
         public_inputs: PublicInputs,
         commitment: Commitment,
-    ) -> Result<(), ProtocolError> {
+    ) -> Result<TransitionReceipt, ProtocolError> {
         // 1. Check state root
         if public_inputs.old_state_root != self.state.state_root {
             return Err(ProtocolError::StateMismatch);
@@ -40,20 +121,129 @@ impl VerifierEngine {
             return Err(ProtocolError::InvalidNonce);
         }
 
-        // 3. Verify proof
-        self.backend.verify(proof_bytes, &public_inputs)?;
+        // 3. Route to the epoch in force at this nonce
+        let epoch_id = self.schedule.epoch_at(self.state.epoch, public_inputs.nonce);
+        let epoch = self
+            .schedule
+            .get(epoch_id)
+            .expect("scheduled epochs are registered");
 
+        // 4. Check the submitted new state commitment against the root
+        //    derived from the old root, nonce and proven payload digest
+        let next_state_root = epoch.backend.next_state_root(&public_inputs)?;
+        if commitment.0 != next_state_root {
+            return Err(ProtocolError::CommitmentMismatch);
+        }
 
+        // 5. Verify proof
+        let started = Instant::now();
+        let policy = epoch.backend.verify(proof_bytes, &public_inputs)?;
+        let duration = started.elapsed();
 
-        // 4. Enforce policy
-        policy::enforce(&public_inputs)?;
+        // 6. Enforce the epoch's policies
+        epoch.policies.enforce(&public_inputs, policy)?;
 
-        // 5. Update state
-        self.state.state_root = commitment.0;
+        // 7. Update state to the root the proof transitions to
+        let previous_state = self.state.clone();
+        self.state.state_root = next_state_root;
         self.state.nonce += 1;
+        self.state.epoch = epoch_id;
 
-        Ok(())
+        // 8. Commit; the transition is only accepted once the store has it
+        if self.store.save(&self.stored()).is_err() {
+            self.state = previous_state;
+            return Err(ProtocolError::StorageFailure);
+        }
+
+        let proof_hash: Hash = Sha256::digest(proof_bytes).into();
+        let transition_hash =
+            transition_hash(&previous_state, &self.state, &proof_hash, &public_inputs);
+
+        Ok(TransitionReceipt {
+            previous_state,
+            new_state: self.state.clone(),
+            proof_hash,
+            backend: epoch.backend.name(),
+            policy,
+            duration,
+            transition_hash,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum OpenError {
+    Storage(StorageError),
+    Schedule(ScheduleError),
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Storage(err) => write!(f, "cannot load state: {}", err),
+            Self::Schedule(err) => write!(f, "cannot resume state: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for OpenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Storage(err) => Some(err),
+            Self::Schedule(err) => Some(err),
+        }
+    }
+}
+
+impl From<StorageError> for OpenError {
+    fn from(err: StorageError) -> Self {
+        Self::Storage(err)
+    }
+}
+
+impl From<ScheduleError> for OpenError {
+    fn from(err: ScheduleError) -> Self {
+        Self::Schedule(err)
+    }
+}
+
+/// Record of an accepted transition
+#[derive(Debug, Clone)]
+pub struct TransitionReceipt {
+    pub previous_state: ProtocolState,
+    pub new_state: ProtocolState,
+    /// SHA-256 of the submitted proof bytes
+    pub proof_hash: Hash,
+    /// `ProofBackend::name` of the backend that verified the proof
+    pub backend: &'static str,
+    /// Rule the backend reported the proof attests
+    pub policy: PolicyKind,
+    /// Time spent in proof verification
+    pub duration: Duration,
+    /// Digest binding both states, the proof and the public inputs
+    ///
+    /// Deterministic: the same transition always hashes the same.
+    pub transition_hash: Hash,
+}
+
+fn transition_hash(
+    previous: &ProtocolState,
+    new: &ProtocolState,
+    proof_hash: &Hash,
+    inputs: &PublicInputs,
+) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(b"zkcg/transition-receipt/v1");
+    for state in [previous, new] {
+        hasher.update(state.state_root);
+        hasher.update(state.nonce.to_le_bytes());
+        hasher.update(state.epoch.to_le_bytes());
     }
+    hasher.update(proof_hash);
+    hasher.update(inputs.threshold.to_le_bytes());
+    hasher.update(inputs.payload_commitment);
+    hasher.update(inputs.dataset_root);
+    hasher.finalize().into()
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -61,4 +251,8 @@ pub struct PublicInputs {
     pub threshold: u64,
     pub old_state_root: [u8; 32],
     pub nonce: u64,
+    /// Digest of the private inputs, as committed in the proof
+    pub payload_commitment: Hash,
+    /// Root of the dataset the proven record belongs to
+    pub dataset_root: Hash,
 }
@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Bound;
+
+use crate::backend::ProofBackend;
+use crate::policy::PolicySet;
+
+/// Verification logic in force during one epoch
+pub struct Epoch {
+    pub backend: Box<dyn ProofBackend>,
+    pub policies: PolicySet,
+    /// Circuit or guest version this epoch's proofs are produced by
+    pub version: String,
+}
+
+impl Epoch {
+    pub fn new(backend: Box<dyn ProofBackend>, version: impl Into<String>) -> Self {
+        Self {
+            backend,
+            policies: PolicySet::default(),
+            version: version.into(),
+        }
+    }
+
+    pub fn with_policies(mut self, policies: PolicySet) -> Self {
+        self.policies = policies;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleError {
+    /// No backend is registered for the epoch
+    UnknownEpoch(u64),
+    /// The epoch is already registered; epochs are never replaced
+    AlreadyRegistered(u64),
+    /// The bump would not move epochs strictly forward in nonce order
+    OutOfOrder { epoch: u64, at_nonce: u64 },
+    /// The bump could not be persisted and was not scheduled
+    StorageFailure,
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownEpoch(epoch) => write!(f, "unknown epoch {}", epoch),
+            Self::AlreadyRegistered(epoch) => write!(f, "epoch {} is already registered", epoch),
+            Self::OutOfOrder { epoch, at_nonce } => {
+                write!(f, "epoch {} at nonce {} is out of order", epoch, at_nonce)
+            }
+            Self::StorageFailure => write!(f, "failed to persist the epoch schedule"),
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+/// Registered epochs and the nonces at which they take effect
+///
+/// A bump scheduled at nonce `n` routes the transition carrying nonce
+/// `n`, and every later one, to the new epoch. Epochs only move forward.
+#[derive(Default)]
+pub struct EpochSchedule {
+    epochs: BTreeMap<u64, Epoch>,
+    activations: BTreeMap<u64, u64>,
+}
+
+impl EpochSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `epoch` under `id`
+    ///
+    /// # Panics
+    ///
+    /// If `id` is already registered.
+    pub fn with_epoch(mut self, id: u64, epoch: Epoch) -> Self {
+        if let Err(err) = self.insert(id, epoch) {
+            panic!("{}", err);
+        }
+        self
+    }
+
+    /// Register `epoch` under `id`, unless `id` is already taken
+    pub fn insert(&mut self, id: u64, epoch: Epoch) -> Result<(), ScheduleError> {
+        if self.epochs.contains_key(&id) {
+            return Err(ScheduleError::AlreadyRegistered(id));
+        }
+        self.epochs.insert(id, epoch);
+        Ok(())
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Epoch> {
+        self.epochs.get(&id)
+    }
+
+    /// Activate epoch `epoch` from nonce `at_nonce` on
+    pub fn schedule(&mut self, epoch: u64, at_nonce: u64) -> Result<(), ScheduleError> {
+        if !self.epochs.contains_key(&epoch) {
+            return Err(ScheduleError::UnknownEpoch(epoch));
+        }
+
+        let before = self.activations.range(..at_nonce).next_back();
+        let after = self
+            .activations
+            .range((Bound::Excluded(at_nonce), Bound::Unbounded))
+            .next();
+
+        if self.activations.contains_key(&at_nonce)
+            || before.is_some_and(|(_, e)| *e >= epoch)
+            || after.is_some_and(|(_, e)| *e <= epoch)
+        {
+            return Err(ScheduleError::OutOfOrder { epoch, at_nonce });
+        }
+
+        self.activations.insert(at_nonce, epoch);
+        Ok(())
+    }
+
+    /// Drop the bump scheduled at `at_nonce`, if any
+    pub(crate) fn unschedule(&mut self, at_nonce: u64) {
+        self.activations.remove(&at_nonce);
+    }
+
+    /// Scheduled bumps, `at_nonce -> epoch`
+    pub fn activations(&self) -> &BTreeMap<u64, u64> {
+        &self.activations
+    }
+
+    /// Epoch a transition carrying `nonce` is verified under
+    pub fn epoch_at(&self, current: u64, nonce: u64) -> u64 {
+        self.activations
+            .range(..=nonce)
+            .next_back()
+            .map(|(_, epoch)| *epoch)
+            .filter(|epoch| *epoch > current)
+            .unwrap_or(current)
+    }
+}
@@ -1,4 +1,5 @@
 pub mod engine;
+pub mod epoch;
 pub mod policy;
 pub mod proof;
 pub mod storage;
@@ -1,5 +1,5 @@
-use zkcg_common::errors::ProtocolError;
-use crate::engine::PublicInputs;
+use zkcg_common::{errors::ProtocolError, types::Hash};
+use crate::{backend::PolicyKind, engine::PublicInputs};
 
 pub fn enforce(inputs: &PublicInputs) -> Result<(), ProtocolError> {
     // Phase 1: placeholder
@@ -11,3 +11,50 @@ pub fn enforce(inputs: &PublicInputs) -> Result<(), ProtocolError> {
 
     Ok(())
 }
+
+/// A policy rule checked against the public inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// The Phase 1 rule of `enforce`
+    Phase1,
+    /// The threshold may not exceed this value
+    MaxThreshold(u64),
+    /// The proven record must belong to this dataset; only proofs that
+    /// attest membership can satisfy it
+    Dataset(Hash),
+}
+
+impl Policy {
+    /// Check the rule for inputs a backend accepted as proving `kind`
+    pub fn enforce(&self, inputs: &PublicInputs, kind: PolicyKind) -> Result<(), ProtocolError> {
+        let ok = match self {
+            Policy::Phase1 => return enforce(inputs),
+            Policy::MaxThreshold(max) => inputs.threshold <= *max,
+            Policy::Dataset(root) => {
+                kind == PolicyKind::Membership && inputs.dataset_root == *root
+            }
+        };
+
+        if ok {
+            Ok(())
+        } else {
+            Err(ProtocolError::PolicyViolation)
+        }
+    }
+}
+
+/// Policies an epoch enforces; all of them must hold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicySet(pub Vec<Policy>);
+
+impl Default for PolicySet {
+    fn default() -> Self {
+        Self(vec![Policy::Phase1])
+    }
+}
+
+impl PolicySet {
+    pub fn enforce(&self, inputs: &PublicInputs, kind: PolicyKind) -> Result<(), ProtocolError> {
+        self.0.iter().try_for_each(|policy| policy.enforce(inputs, kind))
+    }
+}
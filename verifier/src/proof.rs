@@ -1,8 +1,7 @@
+use std::path::Path;
+
 use zkcg_common::errors::ProtocolError;
-use crate::{
-    engine::PublicInputs,
-    backend::ProofBackend,
-};
+use crate::engine::PublicInputs;
 
 
 pub struct ProofInput<'a> {
@@ -10,27 +9,25 @@ pub struct ProofInput<'a> {
     pub public_inputs: &'a PublicInputs,
 }
 
+/// Verify `proof` against the artifact file shared with the prover
 pub fn verify(
     proof: ProofInput<'_>,
+    artifacts_path: &Path,
 ) -> Result<(), ProtocolError> {
     #[cfg(feature = "zk-halo2")]
     {
-        use crate::backend_halo2::Halo2Backend;
-        
-
-        let artifacts = circuits::halo2_artifacts::verifier_artifacts();
-
+        use crate::{backend::ProofBackend, backend_halo2::Halo2Backend};
 
-        let backend = Halo2Backend {
-            vk: artifacts.vk,
-            params: artifacts.params,
-        };
+        // no proof verifies against artifacts that fail to load
+        let backend = Halo2Backend::read_from(artifacts_path)
+            .map_err(|_| ProtocolError::InvalidProof)?;
 
-        return backend.verify(proof.proof_bytes, proof.public_inputs);
+        backend.verify(proof.proof_bytes, proof.public_inputs).map(|_| ())
     }
 
     #[cfg(not(feature = "zk-halo2"))]
     {
+        let _ = (proof, artifacts_path);
         Ok(())
     }
 }
@@ -1,23 +1,245 @@
+use sha2::{Digest, Sha256};
 use zkcg_common::state::ProtocolState;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
+#[derive(Debug)]
+pub enum StorageError {
+    Io(io::Error),
+    /// No state was ever stored at the path (see `FileStateStore::init`)
+    Missing(PathBuf),
+    /// `FileStateStore::init` found a state already stored at the path
+    AlreadyExists(PathBuf),
+    /// The stored state is truncated or fails its checksum
+    Corrupted(PathBuf),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "state storage i/o failed: {}", err),
+            Self::Missing(path) => write!(f, "state file {} does not exist", path.display()),
+            Self::AlreadyExists(path) => {
+                write!(f, "state file {} already exists", path.display())
+            }
+            Self::Corrupted(path) => write!(f, "state file {} is corrupted", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Missing(_) | Self::AlreadyExists(_) | Self::Corrupted(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for StorageError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Everything a verifier must recover after a restart
+#[derive(Debug, Clone)]
+pub struct StoredState {
+    pub state: ProtocolState,
+    /// Scheduled epoch bumps, `at_nonce -> epoch`
+    /// (see `EpochSchedule::schedule`)
+    pub activations: BTreeMap<u64, u64>,
+}
+
+impl StoredState {
+    /// `state` with no epoch bumps scheduled
+    pub fn new(state: ProtocolState) -> Self {
+        Self {
+            state,
+            activations: BTreeMap::new(),
+        }
+    }
+}
+
+/// Durable home of the protocol state
+///
+/// `VerifierEngine` loads its state from the store on startup and
+/// commits every transition and epoch bump through `save` before
+/// accepting it.
+pub trait StateStore: Send + Sync {
+    fn load(&self) -> Result<StoredState, StorageError>;
+
+    /// Persist `stored`; once this returns `Ok` it must survive a restart
+    ///
+    /// An `Err` means the previously stored record is still in place:
+    /// the engine rolls the transition back on it.
+    fn save(&self, stored: &StoredState) -> Result<(), StorageError>;
+}
+
+/// In-memory store; cloned handles share the same state
 #[derive(Clone)]
-pub struct StateStore {
-    inner: Arc<Mutex<ProtocolState>>,
+pub struct MemoryStateStore {
+    inner: Arc<Mutex<StoredState>>,
 }
 
-impl StateStore {
+impl MemoryStateStore {
     pub fn new(state: ProtocolState) -> Self {
         Self {
-            inner: Arc::new(Mutex::new(state)),
+            inner: Arc::new(Mutex::new(StoredState::new(state))),
         }
     }
+}
+
+impl StateStore for MemoryStateStore {
+    fn load(&self) -> Result<StoredState, StorageError> {
+        Ok(self.inner.lock().unwrap().clone())
+    }
 
-    pub fn load(&self) -> ProtocolState {
-        self.inner.lock().unwrap().clone()
+    fn save(&self, stored: &StoredState) -> Result<(), StorageError> {
+        *self.inner.lock().unwrap() = stored.clone();
+        Ok(())
     }
+}
+
+/// Store persisting the state to a single file
+///
+/// Layout: `MAGIC || state_root || nonce || epoch || count || activations
+/// || checksum`, integers little-endian, `count` activations of
+/// `at_nonce || epoch` each, and the checksum being SHA-256 over
+/// everything before it. Saves write a
+/// sibling temp file, fsync it and rename it over the state file, so a
+/// crash leaves either the old or the new state in place.
+///
+/// Genesis is only ever written by `init`. A missing, truncated or
+/// mismatching file is an error rather than a silent reset, so a lost or
+/// misconfigured path cannot reopen replay of old nonces.
+pub struct FileStateStore {
+    path: PathBuf,
+}
+
+impl FileStateStore {
+    const MAGIC: [u8; 8] = *b"ZKCGST02";
+    const HEADER_LEN: usize = Self::MAGIC.len() + 32 + 8 + 8 + 8;
+    const ACTIVATION_LEN: usize = 8 + 8;
+
+    /// Store over the state already written at `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Store over a new state file at `path`, holding genesis
+    ///
+    /// Refuses to overwrite an existing file.
+    pub fn init(path: impl Into<PathBuf>) -> Result<Self, StorageError> {
+        let store = Self::new(path);
+        if store.path.try_exists()? {
+            return Err(StorageError::AlreadyExists(store.path));
+        }
+        store.save(&StoredState::new(ProtocolState::genesis()))?;
+        Ok(store)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn temp_path(&self) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(".tmp");
+        PathBuf::from(name)
+    }
+
+    fn encode(stored: &StoredState) -> Vec<u8> {
+        let state = &stored.state;
+        let mut bytes = Vec::with_capacity(
+            Self::HEADER_LEN + stored.activations.len() * Self::ACTIVATION_LEN + 32,
+        );
+        bytes.extend_from_slice(&Self::MAGIC);
+        bytes.extend_from_slice(&state.state_root);
+        bytes.extend_from_slice(&state.nonce.to_le_bytes());
+        bytes.extend_from_slice(&state.epoch.to_le_bytes());
+        bytes.extend_from_slice(&(stored.activations.len() as u64).to_le_bytes());
+        for (at_nonce, epoch) in &stored.activations {
+            bytes.extend_from_slice(&at_nonce.to_le_bytes());
+            bytes.extend_from_slice(&epoch.to_le_bytes());
+        }
+
+        let checksum = Sha256::digest(&bytes);
+        bytes.extend_from_slice(&checksum);
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Option<StoredState> {
+        let body_len = bytes.len().checked_sub(32)?;
+        if body_len < Self::HEADER_LEN {
+            return None;
+        }
+
+        let (body, checksum) = bytes.split_at(body_len);
+        if body[..8] != Self::MAGIC || Sha256::digest(body).as_slice() != checksum {
+            return None;
+        }
+
+        let word = |bytes: &[u8]| u64::from_le_bytes(bytes.try_into().expect("8-byte slice"));
+        let count = usize::try_from(word(&body[56..64])).ok()?;
+        let activations = &body[Self::HEADER_LEN..];
+        if count.checked_mul(Self::ACTIVATION_LEN) != Some(activations.len()) {
+            return None;
+        }
+
+        Some(StoredState {
+            state: ProtocolState {
+                state_root: body[8..40].try_into().ok()?,
+                nonce: word(&body[40..48]),
+                epoch: word(&body[48..56]),
+            },
+            activations: activations
+                .chunks_exact(Self::ACTIVATION_LEN)
+                .map(|pair| (word(&pair[..8]), word(&pair[8..])))
+                .collect(),
+        })
+    }
+}
+
+impl StateStore for FileStateStore {
+    fn load(&self) -> Result<StoredState, StorageError> {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(StorageError::Missing(self.path.clone()));
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        Self::decode(&bytes).ok_or_else(|| StorageError::Corrupted(self.path.clone()))
+    }
+
+    fn save(&self, stored: &StoredState) -> Result<(), StorageError> {
+        let temp = self.temp_path();
+
+        let mut file = File::create(&temp)?;
+        file.write_all(&Self::encode(stored))?;
+        file.sync_all()?;
+        drop(file);
+
+        // the rename is the commit point: from here on the new state is
+        // the stored one, so nothing after it may report a failed save
+        fs::rename(&temp, &self.path)?;
+
+        // best effort at making the rename itself durable
+        #[cfg(unix)]
+        {
+            let dir = match self.path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+            let _ = File::open(dir).and_then(|dir| dir.sync_all());
+        }
 
-    pub fn save(&self, state: ProtocolState) {
-        *self.inner.lock().unwrap() = state;
+        Ok(())
     }
 }
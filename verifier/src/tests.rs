@@ -1,15 +1,64 @@
     use crate::engine::{PublicInputs, VerifierEngine};
     use zkcg_common::{
-        state::ProtocolState,
+        state::{next_state_root, ProtocolState},
         types::Commitment,
         errors::ProtocolError,
     };
+    use sha2::{Digest, Sha256};
+    use crate::backend::{PolicyKind, ProofBackend};
     use crate::backend_stub::StubBackend;
+    use crate::epoch::{Epoch, EpochSchedule, ScheduleError};
+    use crate::policy::{Policy, PolicySet};
+    use crate::storage::{FileStateStore, MemoryStateStore, StateStore, StorageError, StoredState};
+    use crate::engine::OpenError;
+
+    /// Backend rejecting every proof, to tell epochs apart
+    struct RejectingBackend;
+
+    impl ProofBackend for RejectingBackend {
+        fn name(&self) -> &'static str {
+            "rejecting"
+        }
+
+        fn verify(
+            &self,
+            _proof_bytes: &[u8],
+            _public_inputs: &PublicInputs,
+        ) -> Result<PolicyKind, ProtocolError> {
+            Err(ProtocolError::InvalidProof)
+        }
+    }
+
+    /// Store whose writes always fail
+    struct FailingStore;
+
+    impl StateStore for FailingStore {
+        fn load(&self) -> Result<StoredState, StorageError> {
+            Ok(StoredState::new(ProtocolState::genesis()))
+        }
+
+        fn save(&self, _stored: &StoredState) -> Result<(), StorageError> {
+            Err(std::io::Error::other("disk full").into())
+        }
+    }
+
+    fn stub_schedule() -> EpochSchedule {
+        EpochSchedule::new().with_epoch(0, Epoch::new(Box::new(StubBackend), "v1"))
+    }
 
     fn dummy_commitment() -> Commitment {
         Commitment([42u8; 32])
     }
 
+    /// New state commitment a client must submit for `inputs`
+    fn next_commitment(inputs: &PublicInputs) -> Commitment {
+        Commitment(next_state_root(
+            &inputs.old_state_root,
+            inputs.nonce,
+            &inputs.payload_commitment,
+        ))
+    }
+
     fn initial_state() -> ProtocolState {
         ProtocolState::genesis()
     }
@@ -19,6 +68,8 @@
             threshold: 10,
             old_state_root: state.state_root,
             nonce: state.nonce + 1,
+            payload_commitment: dummy_commitment().0,
+            dataset_root: [0u8; 32],
         }
     }
 
@@ -32,7 +83,7 @@
 
 
         let inputs = valid_inputs(&state);
-        let commitment = dummy_commitment();
+        let commitment = next_commitment(&inputs);
 
         let result = engine.process_transition(
             b"valid-proof",
@@ -57,7 +108,7 @@
         let mut inputs = valid_inputs(&state);
         inputs.nonce = state.nonce; // replay attempt
 
-        let commitment = dummy_commitment();
+        let commitment = next_commitment(&inputs);
 
         let err = engine.process_transition(
             b"valid-proof",
@@ -81,7 +132,7 @@
         let mut inputs = valid_inputs(&state);
         inputs.old_state_root = [1u8; 32]; // forged root
 
-        let commitment = dummy_commitment();
+        let commitment = next_commitment(&inputs);
 
         let err = engine.process_transition(
             b"valid-proof",
@@ -106,7 +157,7 @@
         let mut inputs = valid_inputs(&state);
         inputs.threshold = 0; // violates policy
 
-        let commitment = dummy_commitment();
+        let commitment = next_commitment(&inputs);
 
         let err = engine.process_transition(
             b"valid-proof",
@@ -118,6 +169,52 @@
         assert!(matches!(err, ProtocolError::PolicyViolation));
     }
 
+    #[test]
+    fn commitment_other_than_next_root_is_rejected() {
+        let state = initial_state();
+        let mut engine = VerifierEngine::new(
+        state.clone(),
+        Box::new(StubBackend::default()),
+    );
+
+
+        let inputs = valid_inputs(&state);
+        let commitment = Commitment([7u8; 32]); // not the root the transition reaches
+
+        let err = engine.process_transition(
+            b"valid-proof",
+            inputs,
+            commitment,
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, ProtocolError::CommitmentMismatch));
+        assert_eq!(engine.state().nonce, state.nonce);
+    }
+
+    #[test]
+    fn payload_digest_as_commitment_is_rejected() {
+        let state = initial_state();
+        let mut engine = VerifierEngine::new(
+        state.clone(),
+        Box::new(StubBackend::default()),
+    );
+
+
+        let inputs = valid_inputs(&state);
+        let commitment = dummy_commitment(); // the payload digest, not the next root
+
+        let err = engine.process_transition(
+            b"valid-proof",
+            inputs,
+            commitment,
+        )
+        .unwrap_err();
+
+        assert!(matches!(err, ProtocolError::CommitmentMismatch));
+        assert_eq!(engine.state().nonce, state.nonce);
+    }
+
     #[test]
     fn state_updates_after_valid_transition() {
         let state = initial_state();
@@ -128,7 +225,7 @@
 
 
         let inputs = valid_inputs(&state);
-        let commitment = dummy_commitment();
+        let commitment = next_commitment(&inputs);
 
         let result = engine.process_transition(
             b"valid-proof",
@@ -136,17 +233,372 @@
             commitment.clone(),
         );
         result.unwrap();
-        #[cfg(not(feature = "zk-halo2"))]
-        {
-            let updated = engine.state();
-
-            assert_eq!(updated.nonce, 1);
-            assert_eq!(updated.state_root, commitment.0);
-        }
 
+        // the root is derived; the request's commitment only has to match it
+        let expected_root = next_state_root(&state.state_root, 1, &dummy_commitment().0);
         let updated = engine.state();
 
         assert_eq!(updated.nonce, 1);
+        assert_eq!(updated.state_root, expected_root);
         assert_eq!(updated.state_root, commitment.0);
     }
     
+
+    #[test]
+    fn receipt_describes_the_transition() {
+        let state = initial_state();
+        let mut engine = VerifierEngine::new(state.clone(), Box::new(StubBackend));
+
+        let inputs = valid_inputs(&state);
+        let receipt = engine
+            .process_transition(b"valid-proof", inputs, next_commitment(&inputs))
+            .unwrap();
+
+        assert_eq!(receipt.previous_state.nonce, state.nonce);
+        assert_eq!(receipt.previous_state.state_root, state.state_root);
+        assert_eq!(receipt.new_state.nonce, engine.state().nonce);
+        assert_eq!(receipt.new_state.state_root, engine.state().state_root);
+        assert_eq!(receipt.proof_hash, <[u8; 32]>::from(Sha256::digest(b"valid-proof")));
+        assert_eq!(receipt.backend, "stub");
+        assert_eq!(receipt.policy, PolicyKind::Score);
+    }
+
+    #[test]
+    fn transition_hash_is_deterministic() {
+        let state = initial_state();
+        let inputs = valid_inputs(&state);
+
+        let run = |proof: &[u8]| {
+            VerifierEngine::new(state.clone(), Box::new(StubBackend))
+                .process_transition(proof, inputs, next_commitment(&inputs))
+                .unwrap()
+                .transition_hash
+        };
+
+        assert_eq!(run(b"valid-proof"), run(b"valid-proof"));
+        assert_ne!(run(b"valid-proof"), run(b"other-proof"));
+    }
+
+    #[test]
+    fn scheduled_epoch_routes_to_its_backend() {
+        let state = initial_state();
+        let schedule = EpochSchedule::new()
+            .with_epoch(0, Epoch::new(Box::new(StubBackend), "v1"))
+            .with_epoch(1, Epoch::new(Box::new(RejectingBackend), "v2"));
+        let mut engine = VerifierEngine::with_schedule(state.clone(), schedule).unwrap();
+
+        engine.schedule_epoch(1, 2).unwrap();
+
+        let inputs = valid_inputs(&state);
+        engine
+            .process_transition(b"valid-proof", inputs, next_commitment(&inputs))
+            .unwrap();
+        assert_eq!(engine.state().epoch, 0);
+
+        // nonce 2 is the first one verified under epoch 1
+        let inputs = valid_inputs(engine.state());
+        let err = engine
+            .process_transition(b"valid-proof", inputs, next_commitment(&inputs))
+            .unwrap_err();
+
+        assert!(matches!(err, ProtocolError::InvalidProof));
+        assert_eq!(engine.state().nonce, 1);
+        assert_eq!(engine.state().epoch, 0);
+    }
+
+    #[test]
+    fn epoch_bump_applies_its_policies() {
+        let state = initial_state();
+        let mut engine = VerifierEngine::new(state.clone(), Box::new(StubBackend));
+        engine
+            .register_epoch(
+                1,
+                Epoch::new(Box::new(StubBackend), "v2")
+                    .with_policies(PolicySet(vec![Policy::Phase1, Policy::MaxThreshold(5)])),
+            )
+            .unwrap();
+        engine.schedule_epoch(1, 1).unwrap();
+
+        let inputs = valid_inputs(&state); // threshold 10
+        let err = engine
+            .process_transition(b"valid-proof", inputs, next_commitment(&inputs))
+            .unwrap_err();
+        assert!(matches!(err, ProtocolError::PolicyViolation));
+
+        let inputs = PublicInputs { threshold: 5, ..valid_inputs(&state) };
+        engine
+            .process_transition(b"valid-proof", inputs, next_commitment(&inputs))
+            .unwrap();
+
+        assert_eq!(engine.state().epoch, 1);
+        assert_eq!(engine.epoch().version, "v2");
+    }
+
+    #[test]
+    fn dataset_policy_requires_membership_proof() {
+        let state = initial_state();
+        let inputs = valid_inputs(&state);
+        let schedule = EpochSchedule::new().with_epoch(
+            0,
+            Epoch::new(Box::new(StubBackend), "v1")
+                .with_policies(PolicySet(vec![Policy::Dataset(inputs.dataset_root)])),
+        );
+        let mut engine = VerifierEngine::with_schedule(state, schedule).unwrap();
+
+        // the stub vouches for the score only, whatever root the inputs claim
+        let err = engine
+            .process_transition(b"valid-proof", inputs, next_commitment(&inputs))
+            .unwrap_err();
+
+        assert!(matches!(err, ProtocolError::PolicyViolation));
+        assert_eq!(engine.state().nonce, 0);
+    }
+
+    #[test]
+    fn epoch_bumps_must_move_forward() {
+        let mut engine = VerifierEngine::new(initial_state(), Box::new(StubBackend));
+
+        assert_eq!(engine.schedule_epoch(1, 5), Err(ScheduleError::UnknownEpoch(1)));
+
+        engine.register_epoch(1, Epoch::new(Box::new(StubBackend), "v2")).unwrap();
+        engine.register_epoch(2, Epoch::new(Box::new(StubBackend), "v3")).unwrap();
+
+        // not ahead of the current state
+        assert!(engine.schedule_epoch(0, 5).is_err());
+        assert!(engine.schedule_epoch(1, 0).is_err());
+
+        engine.schedule_epoch(2, 5).unwrap();
+        assert_eq!(
+            engine.schedule_epoch(1, 8),
+            Err(ScheduleError::OutOfOrder { epoch: 1, at_nonce: 8 })
+        );
+        engine.schedule_epoch(1, 3).unwrap();
+
+        // one bump per nonce
+        assert_eq!(
+            engine.schedule_epoch(2, 3),
+            Err(ScheduleError::OutOfOrder { epoch: 2, at_nonce: 3 })
+        );
+    }
+
+    #[test]
+    fn epoch_bump_at_last_nonce_is_scheduled() {
+        let mut engine = VerifierEngine::new(initial_state(), Box::new(StubBackend));
+        engine.register_epoch(1, Epoch::new(Box::new(StubBackend), "v2")).unwrap();
+        engine.register_epoch(2, Epoch::new(Box::new(StubBackend), "v3")).unwrap();
+
+        engine.schedule_epoch(2, u64::MAX).unwrap();
+        assert_eq!(
+            engine.schedule_epoch(2, 5),
+            Err(ScheduleError::OutOfOrder { epoch: 2, at_nonce: 5 })
+        );
+        engine.schedule_epoch(1, 5).unwrap();
+    }
+
+    #[test]
+    fn registered_epoch_cannot_be_replaced() {
+        let mut engine = VerifierEngine::new(initial_state(), Box::new(StubBackend));
+
+        // the current epoch
+        assert_eq!(
+            engine.register_epoch(0, Epoch::new(Box::new(RejectingBackend), "v1")),
+            Err(ScheduleError::AlreadyRegistered(0))
+        );
+        assert_eq!(engine.epoch().version, "default");
+
+        engine.register_epoch(1, Epoch::new(Box::new(StubBackend), "v2")).unwrap();
+        assert_eq!(
+            engine.register_epoch(1, Epoch::new(Box::new(RejectingBackend), "v2")),
+            Err(ScheduleError::AlreadyRegistered(1))
+        );
+    }
+
+    #[test]
+    fn failed_save_drops_scheduled_epoch() {
+        let schedule = stub_schedule().with_epoch(1, Epoch::new(Box::new(StubBackend), "v2"));
+        let mut engine = VerifierEngine::open(Box::new(FailingStore), schedule).unwrap();
+
+        assert_eq!(engine.schedule_epoch(1, 2), Err(ScheduleError::StorageFailure));
+        // not left behind to block a retry
+        assert_eq!(engine.schedule_epoch(1, 2), Err(ScheduleError::StorageFailure));
+    }
+
+    #[test]
+    fn schedule_without_current_epoch_is_rejected() {
+        let state = ProtocolState { epoch: 3, ..initial_state() };
+        let schedule = EpochSchedule::new().with_epoch(0, Epoch::new(Box::new(StubBackend), "v1"));
+
+        assert!(matches!(
+            VerifierEngine::with_schedule(state, schedule),
+            Err(ScheduleError::UnknownEpoch(3))
+        ));
+    }
+
+    #[test]
+    fn engine_resumes_from_store() {
+        let stored = ProtocolState { state_root: [3u8; 32], nonce: 5, epoch: 0 };
+        let store = MemoryStateStore::new(stored.clone());
+        let engine = VerifierEngine::open(Box::new(store), stub_schedule()).unwrap();
+
+        assert_eq!(engine.state().state_root, stored.state_root);
+        assert_eq!(engine.state().nonce, stored.nonce);
+    }
+
+    #[test]
+    fn accepted_transition_is_committed_to_store() {
+        let store = MemoryStateStore::new(initial_state());
+        let mut engine = VerifierEngine::open(Box::new(store.clone()), stub_schedule()).unwrap();
+
+        let inputs = valid_inputs(engine.state());
+        let receipt = engine
+            .process_transition(b"valid-proof", inputs, next_commitment(&inputs))
+            .unwrap();
+
+        let saved = store.load().unwrap().state;
+        assert_eq!(saved.nonce, 1);
+        assert_eq!(saved.state_root, receipt.new_state.state_root);
+    }
+
+    #[test]
+    fn failed_save_rolls_back_transition() {
+        let state = initial_state();
+        let mut engine = VerifierEngine::open(Box::new(FailingStore), stub_schedule()).unwrap();
+
+        let inputs = valid_inputs(&state);
+        let err = engine
+            .process_transition(b"valid-proof", inputs, next_commitment(&inputs))
+            .unwrap_err();
+
+        assert!(matches!(err, ProtocolError::StorageFailure));
+        assert_eq!(engine.state().nonce, state.nonce);
+        assert_eq!(engine.state().state_root, state.state_root);
+    }
+
+    /// Fresh state file path under the system temp dir
+    fn state_path(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("zkcg-{}-{}.bin", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn file_store_survives_restart() {
+        let path = state_path("restart");
+        let mut engine =
+            VerifierEngine::open(Box::new(FileStateStore::init(&path).unwrap()), stub_schedule())
+                .unwrap();
+
+        let inputs = valid_inputs(engine.state());
+        engine
+            .process_transition(b"valid-proof", inputs, next_commitment(&inputs))
+            .unwrap();
+        let committed = engine.state().clone();
+        drop(engine);
+
+        let mut engine =
+            VerifierEngine::open(Box::new(FileStateStore::new(&path)), stub_schedule()).unwrap();
+        assert_eq!(engine.state().state_root, committed.state_root);
+        assert_eq!(engine.state().nonce, 1);
+
+        // replaying nonce 1 after the restart fails
+        let err = engine
+            .process_transition(b"valid-proof", inputs, next_commitment(&inputs))
+            .unwrap_err();
+        assert!(matches!(err, ProtocolError::StateMismatch));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn scheduled_epoch_survives_restart() {
+        let path = state_path("schedule");
+        let schedule = || {
+            stub_schedule().with_epoch(1, Epoch::new(Box::new(RejectingBackend), "v2"))
+        };
+        let mut engine =
+            VerifierEngine::open(Box::new(FileStateStore::init(&path).unwrap()), schedule()).unwrap();
+        engine.schedule_epoch(1, 2).unwrap();
+        drop(engine);
+
+        // the restored bump needs its epoch registered
+        assert!(matches!(
+            VerifierEngine::open(Box::new(FileStateStore::new(&path)), stub_schedule()),
+            Err(OpenError::Schedule(ScheduleError::UnknownEpoch(1)))
+        ));
+
+        let mut engine =
+            VerifierEngine::open(Box::new(FileStateStore::new(&path)), schedule()).unwrap();
+        let inputs = valid_inputs(engine.state());
+        engine
+            .process_transition(b"valid-proof", inputs, next_commitment(&inputs))
+            .unwrap();
+
+        // nonce 2 is still verified under epoch 1
+        let inputs = valid_inputs(engine.state());
+        let err = engine
+            .process_transition(b"valid-proof", inputs, next_commitment(&inputs))
+            .unwrap_err();
+        assert!(matches!(err, ProtocolError::InvalidProof));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_state_file_is_refused() {
+        let path = state_path("missing");
+
+        assert!(matches!(
+            FileStateStore::new(&path).load(),
+            Err(StorageError::Missing(_))
+        ));
+        assert!(matches!(
+            VerifierEngine::open(Box::new(FileStateStore::new(&path)), stub_schedule()),
+            Err(OpenError::Storage(StorageError::Missing(_)))
+        ));
+    }
+
+    #[test]
+    fn init_writes_genesis_once() {
+        let path = state_path("init");
+        let store = FileStateStore::init(&path).unwrap();
+
+        let state = store.load().unwrap().state;
+        assert_eq!(state.nonce, 0);
+        assert_eq!(state.state_root, ProtocolState::genesis().state_root);
+
+        store
+            .save(&StoredState::new(ProtocolState { state_root: [5u8; 32], nonce: 9, epoch: 0 }))
+            .unwrap();
+        assert!(matches!(
+            FileStateStore::init(&path),
+            Err(StorageError::AlreadyExists(_))
+        ));
+        assert_eq!(store.load().unwrap().state.nonce, 9);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn corrupt_state_file_is_refused() {
+        let path = state_path("corrupt");
+        let store = FileStateStore::new(&path);
+        store
+            .save(&StoredState::new(ProtocolState { state_root: [5u8; 32], nonce: 9, epoch: 0 }))
+            .unwrap();
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[12] ^= 1;
+        std::fs::write(&path, &bytes).unwrap();
+
+        assert!(matches!(store.load(), Err(StorageError::Corrupted(_))));
+        assert!(matches!(
+            VerifierEngine::open(Box::new(FileStateStore::new(&path)), stub_schedule()),
+            Err(OpenError::Storage(StorageError::Corrupted(_)))
+        ));
+
+        // a partial write is refused the same way
+        std::fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+        assert!(matches!(store.load(), Err(StorageError::Corrupted(_))));
+
+        std::fs::remove_file(&path).unwrap();
+    }
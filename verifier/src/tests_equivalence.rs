@@ -9,16 +9,23 @@ use crate::backend::ProofBackend;
 use zkcg_common::errors::ProtocolError;
 use rand::rngs::OsRng;
 use halo2_proofs::{
-    circuit::Value,
     plonk::{create_proof, keygen_pk, keygen_vk},
     poly::commitment::Params,
     transcript::{Blake2bWrite, Challenge255},
 };
-use halo2_proofs::arithmetic::Field;
 use halo2curves::bn256::{Fr, G1Affine};
-use circuits::score_circuit::ScoreCircuit;
-use zkcg_zkvm_host::prove;
-use zkcg_common::state::ProtocolState;
+use circuits::{
+    halo2_artifacts::SCORE_CIRCUIT_K,
+    score_circuit::{
+        commitment_from_bytes, commitment_to_bytes, public_instances, score_commitment,
+        DatasetRecord, ScoreCircuit,
+    },
+};
+use zkcg_zkvm_host::prove_dev;
+use zkcg_common::{
+    state::ProtocolState,
+    types::{score_commitment as zkvm_score_commitment, NO_DATASET},
+};
 /* ---------------- Expectations ---------------- */
 
 #[derive(Copy, Clone)]
@@ -59,8 +66,8 @@ fn scenarios() -> Vec<TestScenario> {
 
 /* ---------------- Helpers ---------------- */
 
-fn matches_expectation(
-    result: Result<(), ProtocolError>,
+fn matches_expectation<T>(
+    result: Result<T, ProtocolError>,
     expected: Expectation,
 ) -> bool {
     match expected {
@@ -71,16 +78,48 @@ fn matches_expectation(
 
 /* ---------------- Halo2 ---------------- */
 
-fn halo2_prove(score: u64, threshold: u64, params: &Params<G1Affine>) -> Vec<u8> {
-    let circuit = ScoreCircuit::<Fr> {
-        score: Value::known(Fr::from(score)),
-        threshold: Value::known(Fr::from(threshold)),
-    };
+fn blinding() -> Fr {
+    Fr::from(0xB11D)
+}
+
+fn payload_commitment(score: u64) -> [u8; 32] {
+    commitment_to_bytes(&score_commitment::<Fr>(score, blinding()))
+}
+
+/// Scored record at an explicit position of the test dataset
+fn record() -> DatasetRecord<Fr> {
+    DatasetRecord {
+        id: Fr::from(37),
+        salt: Fr::from(0x5A17),
+        siblings: std::array::from_fn(|level| Fr::from(level as u64 + 1)),
+        index: 37,
+    }
+}
+
+fn dataset_root(score: u64) -> [u8; 32] {
+    commitment_to_bytes(&record().root(score))
+}
+
+fn halo2_prove(score: u64, inputs: &PublicInputs, params: &Params<G1Affine>) -> Vec<u8> {
+    let circuit = ScoreCircuit::<Fr>::new(
+        score,
+        inputs.threshold,
+        inputs.old_state_root,
+        inputs.nonce,
+        blinding(),
+        &record(),
+    );
 
     let vk = keygen_vk(params, &circuit).unwrap();
     let pk = keygen_pk(params, vk, &circuit).unwrap();
 
-    let instances = vec![vec![Fr::from(threshold)]];
+    let instances = [public_instances::<Fr>(
+        inputs.threshold,
+        &inputs.old_state_root,
+        inputs.nonce,
+        commitment_from_bytes(&inputs.payload_commitment).unwrap(),
+        commitment_from_bytes(&inputs.dataset_root).unwrap(),
+    )];
     let instance_refs: Vec<&[Fr]> = instances.iter().map(|v| v.as_slice()).collect();
     let all_instances = vec![instance_refs.as_slice()];
 
@@ -101,11 +140,8 @@ fn halo2_prove(score: u64, threshold: u64, params: &Params<G1Affine>) -> Vec<u8>
 }
 
 fn halo2_backend() -> Halo2Backend {
-    let params = Params::new(9);
-    let dummy = ScoreCircuit::<Fr> {
-        score: Value::known(Fr::ZERO),
-        threshold: Value::known(Fr::ZERO),
-    };
+    let params = Params::new(SCORE_CIRCUIT_K);
+    let dummy = ScoreCircuit::<Fr>::default();
     let vk = keygen_vk(&params, &dummy).unwrap();
     Halo2Backend { vk, params }
 }
@@ -116,6 +152,8 @@ fn test_inputs() -> PublicInputs {
         threshold: 10,
         old_state_root: [0u8; 32], // Match genesis root for simplicity
         nonce: 1, // state.nonce + 1
+        payload_commitment: [0u8; 32],
+        dataset_root: [0u8; 32],
     }
 }
 
@@ -129,9 +167,21 @@ fn mock_state(inputs: &PublicInputs) -> ProtocolState {
 }
 /* ---------------- zkVM ---------------- */
 
+const ZKVM_BLINDING: [u8; 32] = [0u8; 32];
+
+/// The guest commits to the score with SHA-256, not Poseidon, and the
+/// score guest checks no dataset membership
+fn zkvm_inputs(score: u64, inputs: &PublicInputs) -> PublicInputs {
+    PublicInputs {
+        payload_commitment: zkvm_score_commitment(score, &ZKVM_BLINDING),
+        dataset_root: NO_DATASET,
+        ..*inputs
+    }
+}
+
 fn zkvm_prove(score: u64, threshold: u64) -> Result<Vec<u8>, ProtocolError> {
     let inputs = test_inputs();
-    prove(score, threshold, inputs.old_state_root, inputs.nonce).map_err(|_| ProtocolError::InvalidProof)
+    prove_dev(score, threshold, inputs.old_state_root, inputs.nonce, ZKVM_BLINDING).map_err(|_| ProtocolError::InvalidProof)
 }
 
 /* ---------------- Rust baseline ---------------- */
@@ -153,11 +203,13 @@ fn cross_backend_equivalence() {
             threshold: s.threshold,
             old_state_root: [0u8; 32],
             nonce: 1,
+            payload_commitment: payload_commitment(s.score),
+            dataset_root: dataset_root(s.score),
         };
 
         // Halo2
-        let params = Params::new(9);
-        let halo2_proof = halo2_prove(s.score, s.threshold, &params);
+        let params = Params::new(SCORE_CIRCUIT_K);
+        let halo2_proof = halo2_prove(s.score, &inputs, &params);
         let halo2 = halo2_backend();
         let halo2_result = halo2.verify(&halo2_proof, &inputs);
 
@@ -168,9 +220,9 @@ fn cross_backend_equivalence() {
         );
 
         // zkVM
-        let zkvm = ZkVmBackend;
+        let zkvm = ZkVmBackend::new().with_dev_mode(true);
         let zkvm_result = zkvm_prove(s.score, s.threshold)
-            .and_then(|p| zkvm.verify(&p, &inputs));
+            .and_then(|p| zkvm.verify(&p, &zkvm_inputs(s.score, &inputs)));
 
         assert!(
             matches_expectation(zkvm_result, s.expected),
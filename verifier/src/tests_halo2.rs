@@ -3,35 +3,78 @@
 use rand::rngs::OsRng;
 
 use halo2_proofs::{
-    circuit::Value,
     plonk::{create_proof, keygen_pk, keygen_vk},
     poly::commitment::Params,
     transcript::{Blake2bWrite, Challenge255, TranscriptWrite},
 };
-use halo2_proofs::arithmetic::Field;
 use halo2curves::bn256::{Fr, G1Affine};
 
-use circuits::score_circuit::ScoreCircuit;
+use zkcg_common::errors::ProtocolError;
+use zkcg_common::{
+    state::{next_state_root, ProtocolState},
+    types::Commitment,
+};
+use circuits::{
+    halo2_artifacts::SCORE_CIRCUIT_K,
+    score_circuit::{
+        commitment_from_bytes, commitment_to_bytes, public_instances, score_commitment,
+        transition_root, DatasetRecord, ScoreCircuit,
+    },
+};
 use crate::{
     backend::ProofBackend,
     backend_halo2::Halo2Backend,
-    engine::PublicInputs,
+    engine::{PublicInputs, VerifierEngine},
 };
 
-/// Generate a valid Halo2 proof using fresh params
-fn generate_valid_proof(score: u64, threshold: u64) -> Vec<u8> {
-    let circuit = ScoreCircuit::<Fr> {
-        score: Value::known(Fr::from(score)),
-        threshold: Value::known(Fr::from(threshold)),
-    };
+fn blinding() -> Fr {
+    Fr::from(0xB11D)
+}
+
+/// Commitment a proof of `score` exposes
+fn payload_commitment(score: u64) -> [u8; 32] {
+    commitment_to_bytes(&score_commitment::<Fr>(score, blinding()))
+}
+
+/// Scored record at an explicit position of the test dataset
+fn record() -> DatasetRecord<Fr> {
+    DatasetRecord {
+        id: Fr::from(37),
+        salt: Fr::from(0x5A17),
+        siblings: std::array::from_fn(|level| Fr::from(level as u64 + 1)),
+        index: 37,
+    }
+}
 
-    let k = 9;
+/// Dataset root a proof of `score` for `record()` exposes
+fn dataset_root(score: u64) -> [u8; 32] {
+    commitment_to_bytes(&record().root(score))
+}
+
+/// Generate a valid Halo2 proof using fresh params
+fn generate_valid_proof(score: u64, inputs: &PublicInputs) -> Vec<u8> {
+    let circuit = ScoreCircuit::<Fr>::new(
+        score,
+        inputs.threshold,
+        inputs.old_state_root,
+        inputs.nonce,
+        blinding(),
+        &record(),
+    );
+
+    let k = SCORE_CIRCUIT_K;
     let params: Params<G1Affine> = Params::new(k);
 
     let vk = keygen_vk(&params, &circuit).unwrap();
     let pk = keygen_pk(&params, vk, &circuit).unwrap();
 
-    let public_inputs = vec![vec![Fr::from(threshold)]];
+    let public_inputs = [public_instances::<Fr>(
+        inputs.threshold,
+        &inputs.old_state_root,
+        inputs.nonce,
+        commitment_from_bytes(&inputs.payload_commitment).unwrap(),
+        commitment_from_bytes(&inputs.dataset_root).unwrap(),
+    )];
     let instance_slices: Vec<&[Fr]> =
         public_inputs.iter().map(|v| v.as_slice()).collect();
     let all_instances: Vec<&[&[Fr]]> =
@@ -56,18 +99,28 @@ fn generate_valid_proof(score: u64, threshold: u64) -> Vec<u8> {
 /// Generate a valid Halo2 proof using caller-supplied params
 fn generate_valid_proof_with_params(
     score: u64,
-    threshold: u64,
+    inputs: &PublicInputs,
     params: &Params<G1Affine>,
 ) -> Vec<u8> {
-    let circuit = ScoreCircuit::<Fr> {
-        score: Value::known(Fr::from(score)),
-        threshold: Value::known(Fr::from(threshold)),
-    };
+    let circuit = ScoreCircuit::<Fr>::new(
+        score,
+        inputs.threshold,
+        inputs.old_state_root,
+        inputs.nonce,
+        blinding(),
+        &record(),
+    );
 
     let vk = keygen_vk(params, &circuit).unwrap();
     let pk = keygen_pk(params, vk, &circuit).unwrap();
 
-    let public_inputs = vec![vec![Fr::from(threshold)]];
+    let public_inputs = [public_instances::<Fr>(
+        inputs.threshold,
+        &inputs.old_state_root,
+        inputs.nonce,
+        commitment_from_bytes(&inputs.payload_commitment).unwrap(),
+        commitment_from_bytes(&inputs.dataset_root).unwrap(),
+    )];
     let instance_slices: Vec<&[Fr]> =
         public_inputs.iter().map(|v| v.as_slice()).collect();
     let all_instances: Vec<&[&[Fr]]> =
@@ -91,10 +144,7 @@ fn generate_valid_proof_with_params(
 
 /// Construct a Halo2 verifier backend from params
 fn backend(params: Params<G1Affine>) -> Halo2Backend {
-    let dummy = ScoreCircuit::<Fr> {
-        score: Value::known(Fr::ZERO),
-        threshold: Value::known(Fr::ZERO),
-    };
+    let dummy = ScoreCircuit::<Fr>::default();
 
     let vk = keygen_vk(&params, &dummy).unwrap();
     Halo2Backend { vk, params }
@@ -102,51 +152,63 @@ fn backend(params: Params<G1Affine>) -> Halo2Backend {
 
 #[test]
 fn valid_halo2_proof_is_accepted() {
-    let k = 9;
+    let k = SCORE_CIRCUIT_K;
     let params: Params<G1Affine> = Params::new(k);
 
-    let proof = generate_valid_proof_with_params(39, 40, &params);
-    let backend = backend(params);
-
     let inputs = PublicInputs {
         threshold: 40,
         old_state_root: [0u8; 32],
         nonce: 1,
+        payload_commitment: payload_commitment(39),
+        dataset_root: dataset_root(39),
     };
+
+    let proof = generate_valid_proof_with_params(39, &inputs, &params);
+    let backend = backend(params);
+
     assert!(backend.verify(&proof, &inputs).is_ok());
 }
 
 #[test]
 fn modified_proof_is_rejected() {
-    let k = 9;
+    let k = SCORE_CIRCUIT_K;
     let params: Params<G1Affine> = Params::new(k);
 
-    let mut proof = generate_valid_proof_with_params(39, 40, &params);
-    proof[10] ^= 0xFF;
-
-    let backend = backend(params);
-
     let inputs = PublicInputs {
         threshold: 40,
         old_state_root: [0u8; 32],
         nonce: 1,
+        payload_commitment: payload_commitment(39),
+        dataset_root: dataset_root(39),
     };
 
+    let mut proof = generate_valid_proof_with_params(39, &inputs, &params);
+    proof[10] ^= 0xFF;
+
+    let backend = backend(params);
+
     assert!(backend.verify(&proof, &inputs).is_err());
 }
 
 #[test]
 fn wrong_public_input_is_rejected() {
-    let k = 9;
+    let k = SCORE_CIRCUIT_K;
     let params: Params<G1Affine> = Params::new(k);
 
-    let proof = generate_valid_proof_with_params(39, 40, &params);
+    let inputs = PublicInputs {
+        threshold: 40,
+        old_state_root: [0u8; 32],
+        nonce: 1,
+        payload_commitment: payload_commitment(39),
+        dataset_root: dataset_root(39),
+    };
+
+    let proof = generate_valid_proof_with_params(39, &inputs, &params);
     let backend = backend(params);
 
     let wrong_inputs = PublicInputs {
         threshold: 41, // WRONG
-        old_state_root: [0u8; 32],
-        nonce: 1,
+        ..inputs
     };
 
     assert!(backend.verify(&proof, &wrong_inputs).is_err());
@@ -154,7 +216,7 @@ fn wrong_public_input_is_rejected() {
 
 #[test]
 fn empty_proof_is_rejected() {
-    let k = 9;
+    let k = SCORE_CIRCUIT_K;
     let params: Params<G1Affine> = Params::new(k);
 
     let backend = backend(params);
@@ -163,7 +225,217 @@ fn empty_proof_is_rejected() {
         threshold: 40,
         old_state_root: [0u8; 32],
         nonce: 1,
+        payload_commitment: payload_commitment(39),
+        dataset_root: dataset_root(39),
     };
 
     assert!(backend.verify(&[], &inputs).is_err());
-}
\ No newline at end of file
+}
+
+#[test]
+fn proof_replayed_at_next_nonce_is_rejected() {
+    let k = SCORE_CIRCUIT_K;
+    let params: Params<G1Affine> = Params::new(k);
+
+    let inputs = PublicInputs {
+        threshold: 40,
+        old_state_root: [0u8; 32],
+        nonce: 1,
+        payload_commitment: payload_commitment(39),
+        dataset_root: dataset_root(39),
+    };
+
+    let proof = generate_valid_proof_with_params(39, &inputs, &params);
+    let backend = backend(params);
+
+    let replayed = PublicInputs {
+        nonce: inputs.nonce + 1, // WRONG
+        ..inputs
+    };
+
+    assert!(backend.verify(&proof, &inputs).is_ok());
+    assert!(backend.verify(&proof, &replayed).is_err());
+}
+
+#[test]
+fn proof_for_other_state_root_is_rejected() {
+    let k = SCORE_CIRCUIT_K;
+    let params: Params<G1Affine> = Params::new(k);
+
+    let inputs = PublicInputs {
+        threshold: 40,
+        old_state_root: [3u8; 32],
+        nonce: 1,
+        payload_commitment: payload_commitment(39),
+        dataset_root: dataset_root(39),
+    };
+
+    let proof = generate_valid_proof_with_params(39, &inputs, &params);
+    let backend = backend(params);
+
+    let wrong_inputs = PublicInputs {
+        old_state_root: [4u8; 32], // WRONG
+        ..inputs
+    };
+
+    assert!(backend.verify(&proof, &wrong_inputs).is_err());
+}
+
+#[test]
+fn valid_halo2_batch_is_accepted() {
+    let k = SCORE_CIRCUIT_K;
+    let params: Params<G1Affine> = Params::new(k);
+
+    let inputs: Vec<PublicInputs> = (1..=3)
+        .map(|nonce| PublicInputs {
+            threshold: 40,
+            old_state_root: [0u8; 32],
+            nonce,
+            payload_commitment: payload_commitment(39),
+            dataset_root: dataset_root(39),
+        })
+        .collect();
+
+    let proofs: Vec<Vec<u8>> = inputs
+        .iter()
+        .map(|inputs| generate_valid_proof_with_params(39, inputs, &params))
+        .collect();
+    let backend = backend(params);
+
+    let batch: Vec<(&[u8], PublicInputs)> = proofs
+        .iter()
+        .zip(inputs)
+        .map(|(proof, inputs)| (proof.as_slice(), inputs))
+        .collect();
+
+    assert!(backend.verify_batch(&batch).iter().all(|r| r.is_ok()));
+}
+
+#[test]
+fn failing_batch_reports_individual_proofs() {
+    let k = SCORE_CIRCUIT_K;
+    let params: Params<G1Affine> = Params::new(k);
+
+    let inputs: Vec<PublicInputs> = (1..=3)
+        .map(|nonce| PublicInputs {
+            threshold: 40,
+            old_state_root: [0u8; 32],
+            nonce,
+            payload_commitment: payload_commitment(39),
+            dataset_root: dataset_root(39),
+        })
+        .collect();
+
+    let proofs: Vec<Vec<u8>> = inputs
+        .iter()
+        .map(|inputs| generate_valid_proof_with_params(39, inputs, &params))
+        .collect();
+    let backend = backend(params);
+
+    let mut batch: Vec<(&[u8], PublicInputs)> = proofs
+        .iter()
+        .zip(inputs)
+        .map(|(proof, inputs)| (proof.as_slice(), inputs))
+        .collect();
+    batch[1].1.threshold = 41; // WRONG
+
+    let results = backend.verify_batch(&batch);
+
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(ProtocolError::InvalidProof)));
+    assert!(results[2].is_ok());
+}
+
+#[test]
+fn proof_for_other_commitment_is_rejected() {
+    let k = SCORE_CIRCUIT_K;
+    let params: Params<G1Affine> = Params::new(k);
+
+    let inputs = PublicInputs {
+        threshold: 40,
+        old_state_root: [0u8; 32],
+        nonce: 1,
+        payload_commitment: payload_commitment(39),
+        dataset_root: dataset_root(39),
+    };
+
+    let proof = generate_valid_proof_with_params(39, &inputs, &params);
+    let backend = backend(params);
+
+    let wrong_inputs = PublicInputs {
+        payload_commitment: payload_commitment(38), // WRONG
+        ..inputs
+    };
+    let non_canonical = PublicInputs {
+        payload_commitment: [0xFF; 32], // not a field element
+        ..inputs
+    };
+
+    assert!(backend.verify(&proof, &wrong_inputs).is_err());
+    assert!(matches!(
+        backend.verify(&proof, &non_canonical),
+        Err(ProtocolError::InvalidProof)
+    ));
+}
+
+#[test]
+fn engine_adopts_proven_state_root() {
+    let k = SCORE_CIRCUIT_K;
+    let params: Params<G1Affine> = Params::new(k);
+
+    let state = ProtocolState::genesis();
+    let inputs = PublicInputs {
+        threshold: 40,
+        old_state_root: state.state_root,
+        nonce: state.nonce + 1,
+        payload_commitment: payload_commitment(39),
+        dataset_root: dataset_root(39),
+    };
+
+    let proof = generate_valid_proof_with_params(39, &inputs, &params);
+    let mut engine = VerifierEngine::new(state, Box::new(backend(params)));
+
+    let proven_root = commitment_to_bytes(&transition_root::<Fr>(
+        &inputs.old_state_root,
+        inputs.nonce,
+        score_commitment(39, blinding()),
+    ));
+
+    // the SHA-256 transition is not what this backend proves
+    let sha_root = next_state_root(&inputs.old_state_root, inputs.nonce, &inputs.payload_commitment);
+    assert!(matches!(
+        engine.process_transition(&proof, inputs, Commitment(sha_root)),
+        Err(ProtocolError::CommitmentMismatch)
+    ));
+
+    engine
+        .process_transition(&proof, inputs, Commitment(proven_root))
+        .unwrap();
+
+    assert_eq!(engine.state().state_root, proven_root);
+}
+
+#[test]
+fn proof_for_other_dataset_is_rejected() {
+    let k = SCORE_CIRCUIT_K;
+    let params: Params<G1Affine> = Params::new(k);
+
+    let inputs = PublicInputs {
+        threshold: 40,
+        old_state_root: [0u8; 32],
+        nonce: 1,
+        payload_commitment: payload_commitment(39),
+        dataset_root: dataset_root(39),
+    };
+
+    let proof = generate_valid_proof_with_params(39, &inputs, &params);
+    let backend = backend(params);
+
+    let wrong_inputs = PublicInputs {
+        dataset_root: dataset_root(38), // WRONG
+        ..inputs
+    };
+
+    assert!(backend.verify(&proof, &inputs).is_ok());
+    assert!(backend.verify(&proof, &wrong_inputs).is_err());
+}
@@ -1,24 +1,46 @@
 #![cfg(feature = "zk-vm")]
 
 use crate::{
+    backend::{PolicyKind, ProofBackend},
     engine::{PublicInputs, VerifierEngine},
     backend_zkvm::ZkVmBackend,
+    policy::{Policy, PolicySet},
 };
 use zkcg_common::{
+    dataset::{DatasetPath, DatasetRecord},
     errors::ProtocolError,
-    state::ProtocolState,
-    types::Commitment,
+    state::{next_state_root, ProtocolState},
+    types::{
+        features_commitment, score_commitment, Commitment, ModelParams, ZkVmJournal, ZkVmOutput,
+        MODEL_FEATURES, NO_DATASET, NO_MODEL,
+    },
 };
-use zkcg_zkvm_host::{prove, ZkVmProverError};
+use zkcg_zkvm_host::{
+    method_id, prove, prove_chain, prove_chain_with_report, prove_dev, prove_membership,
+    prove_membership_with_report, prove_model, prove_model_with_report, prove_with_report,
+    ProveOptions, ProveReport, ReceiptKind, ZkVmProof, ZkVmProverError,
+};
+use risc0_zkvm::{Digest, InnerReceipt};
+use std::sync::OnceLock;
+
+const BLINDING: [u8; 32] = [42u8; 32];
 
+/// Commitment the guest computes for the score-5 proofs below
 fn commitment() -> Commitment {
-    Commitment([42u8; 32])
+    Commitment(score_commitment(5, &BLINDING))
+}
+
+/// New state commitment a client must submit for `inputs`
+fn next_commitment(inputs: &PublicInputs) -> Commitment {
+    Commitment(next_state_root(&inputs.old_state_root, inputs.nonce, &inputs.payload_commitment))
 }
 fn valid_inputs() -> PublicInputs {
     PublicInputs {
         threshold: 10,
         old_state_root: [9u8; 32],
         nonce: 7,
+        payload_commitment: commitment().0,
+        dataset_root: [0u8; 32],
     }
 }
 // Consistent inputs (override genesis for matching)
@@ -27,9 +49,40 @@ fn test_inputs() -> PublicInputs {
         threshold: 10,
         old_state_root: [0u8; 32], // Match genesis root for simplicity
         nonce: 1, // state.nonce + 1
+        payload_commitment: commitment().0,
+        dataset_root: [0u8; 32],
     }
 }
 
+/// Backend accepting the fake receipts from `prove_dev`
+fn dev_backend() -> ZkVmBackend {
+    ZkVmBackend::new().with_dev_mode(true)
+}
+
+/// Options producing fake receipts, for tests that don't exercise the seal
+fn dev() -> ProveOptions {
+    ProveOptions { dev_mode: true, ..ProveOptions::default() }
+}
+
+/// The one real receipt of this suite: a succinct score-5 proof for
+/// `test_inputs`, proven once and shared by the soundness tests
+fn real_proof() -> &'static [u8] {
+    static PROOF: OnceLock<Vec<u8>> = OnceLock::new();
+    PROOF.get_or_init(|| {
+        let inputs = test_inputs();
+        let options = ProveOptions { receipt_kind: ReceiptKind::Succinct, ..ProveOptions::default() };
+        prove(5, inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING, options)
+            .expect("valid proof generated")
+    })
+}
+
+/// Re-encode a proof envelope after modifying it
+fn tamper(proof: &[u8], modify: impl FnOnce(&mut ZkVmProof)) -> Vec<u8> {
+    let mut envelope: ZkVmProof = bincode::deserialize(proof).unwrap();
+    modify(&mut envelope);
+    bincode::serialize(&envelope).unwrap()
+}
+
 // Helper: Mock state to match inputs (avoids genesis mismatch)
 fn mock_state(inputs: &PublicInputs) -> ProtocolState {
     ProtocolState {
@@ -46,27 +99,29 @@ fn zkvm_valid_transition_succeeds() {
 
     let mut engine = VerifierEngine::new(
         state.clone(),
-        Box::new(ZkVmBackend),
+        Box::new(ZkVmBackend::new()),
     );
 
     // Prove with matching inputs (score=5 <=10)
-    let proof = prove(5, inputs.threshold, inputs.old_state_root, inputs.nonce)
-        .expect("valid proof generated");
-    println!("Generated proof: {:?}", proof);
+    let proof = real_proof();
     let result = engine.process_transition(
-        &proof,
+        proof,
         inputs,
-        commitment(),
+        next_commitment(&inputs),
     );
     println!("Result: {:?}", result);
 
     assert!(result.is_ok(), "Valid transition should succeed");
+    assert_eq!(
+        engine.state().state_root,
+        next_state_root(&inputs.old_state_root, inputs.nonce, &commitment().0),
+    );
 }
 
 #[test]
 fn zkvm_policy_violation_is_rejected() {
     let mut inputs = valid_inputs();
-    let result = prove(20, 10, inputs.old_state_root, inputs.nonce);
+    let result = prove_dev(20, 10, inputs.old_state_root, inputs.nonce, BLINDING);
 
     assert!(matches!(
         result,
@@ -77,26 +132,28 @@ fn zkvm_policy_violation_is_rejected() {
 #[test]
 fn zkvm_tampered_proof_is_rejected() {
     let mut inputs = valid_inputs();
-    let mut proof = prove(5, 10, inputs.old_state_root, inputs.nonce).unwrap();
+    let mut proof = prove_dev(5, 10, inputs.old_state_root, inputs.nonce, BLINDING).unwrap();
 
     proof[0] ^= 0xFF; // corrupt method id
 
     let state = ProtocolState::genesis();
     let mut engine = VerifierEngine::new(
         state.clone(),
-        Box::new(ZkVmBackend),
+        Box::new(dev_backend()),
     );
 
     let inputs = PublicInputs {
         threshold: 10,
         old_state_root: state.state_root,
         nonce: state.nonce + 1,
+        payload_commitment: commitment().0,
+        dataset_root: [0u8; 32],
     };
 
     let result = engine.process_transition(
         &proof,
         inputs,
-        commitment(),
+        next_commitment(&inputs),
     );
 
     assert!(matches!(result, Err(ProtocolError::InvalidProof)));
@@ -107,19 +164,21 @@ fn zkvm_empty_proof_is_rejected() {
     let state = ProtocolState::genesis();
     let mut engine = VerifierEngine::new(
         state.clone(),
-        Box::new(ZkVmBackend),
+        Box::new(ZkVmBackend::new()),
     );
 
     let inputs = PublicInputs {
         threshold: 10,
         old_state_root: state.state_root,
         nonce: state.nonce + 1,
+        payload_commitment: commitment().0,
+        dataset_root: [0u8; 32],
     };
 
     let result = engine.process_transition(
         &[],
         inputs,
-        commitment(),
+        next_commitment(&inputs),
     );
 
     assert!(result.is_err());
@@ -128,6 +187,443 @@ fn zkvm_empty_proof_is_rejected() {
 #[test]
 fn zkvm_overflow_inputs_rejected() {
     let mut inputs = valid_inputs();
-    let result = prove(u64::MAX, u64::MAX - 1, inputs.old_state_root, inputs.nonce);
-    assert!(result.is_err());
+    let result = prove_dev(u64::MAX, u64::MAX - 1, inputs.old_state_root, inputs.nonce, BLINDING);
+    assert!(matches!(result, Err(ZkVmProverError::PolicyViolation)));
+}
+
+#[test]
+fn zkvm_tampered_seal_is_rejected() {
+    let inputs = test_inputs();
+
+    let tampered = tamper(real_proof(), |envelope| match &mut envelope.receipt.inner {
+        InnerReceipt::Composite(receipt) => receipt.segments[0].seal[0] ^= 1,
+        InnerReceipt::Succinct(receipt) => receipt.seal[0] ^= 1,
+        other => panic!("unexpected receipt kind: {:?}", other),
+    });
+
+    let mut engine = VerifierEngine::new(mock_state(&inputs), Box::new(ZkVmBackend::new()));
+    let result = engine.process_transition(&tampered, inputs, next_commitment(&inputs));
+
+    assert!(matches!(result, Err(ProtocolError::InvalidProof)));
+}
+
+#[test]
+fn zkvm_tampered_journal_is_rejected() {
+    let inputs = test_inputs();
+
+    // the seal attests to the journal digest; any edit breaks the claim
+    let tampered = tamper(real_proof(), |envelope| envelope.receipt.journal.bytes[0] ^= 1);
+
+    let mut engine = VerifierEngine::new(mock_state(&inputs), Box::new(ZkVmBackend::new()));
+    let result = engine.process_transition(&tampered, inputs, next_commitment(&inputs));
+
+    assert!(matches!(result, Err(ProtocolError::InvalidProof)));
+}
+
+#[test]
+fn zkvm_method_id_without_receipt_is_rejected() {
+    // the old envelope: just an image ID and a digest
+    let mut forged = bincode::serialize(&method_id()).unwrap();
+    forged.extend_from_slice(&[0u8; 32]);
+
+    let inputs = test_inputs();
+    let mut engine = VerifierEngine::new(mock_state(&inputs), Box::new(ZkVmBackend::new()));
+    let result = engine.process_transition(&forged, inputs, next_commitment(&inputs));
+
+    assert!(matches!(result, Err(ProtocolError::InvalidProof)));
+}
+
+#[test]
+fn zkvm_proof_for_other_threshold_is_rejected() {
+    let inputs = test_inputs();
+    let proof = prove_dev(5, 12, inputs.old_state_root, inputs.nonce, BLINDING).unwrap();
+
+    let mut engine = VerifierEngine::new(mock_state(&inputs), Box::new(dev_backend()));
+    let result = engine.process_transition(&proof, inputs, next_commitment(&inputs));
+
+    assert!(matches!(result, Err(ProtocolError::InvalidProof)));
+}
+
+#[test]
+fn zkvm_proof_for_other_state_is_rejected() {
+    let inputs = test_inputs();
+    let proof = prove_dev(5, inputs.threshold, [1u8; 32], inputs.nonce, BLINDING).unwrap();
+
+    assert!(matches!(
+        dev_backend().verify(&proof, &inputs),
+        Err(ProtocolError::InvalidProof)
+    ));
+}
+
+#[test]
+fn zkvm_proof_for_other_nonce_is_rejected() {
+    let inputs = test_inputs();
+    let proof = prove_dev(5, inputs.threshold, inputs.old_state_root, inputs.nonce + 1, BLINDING).unwrap();
+
+    assert!(matches!(
+        dev_backend().verify(&proof, &inputs),
+        Err(ProtocolError::InvalidProof)
+    ));
+}
+
+#[test]
+fn zkvm_proof_for_other_commitment_is_rejected() {
+    let inputs = test_inputs();
+    let proof = prove_dev(5, inputs.threshold, inputs.old_state_root, inputs.nonce, [7u8; 32]).unwrap();
+
+    let mut engine = VerifierEngine::new(mock_state(&inputs), Box::new(dev_backend()));
+    let result = engine.process_transition(&proof, inputs, next_commitment(&inputs));
+
+    assert!(matches!(result, Err(ProtocolError::InvalidProof)));
+}
+
+#[test]
+fn zkvm_journal_decodes_to_committed_inputs() {
+    let inputs = test_inputs();
+    let proof = prove_dev(5, inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING).unwrap();
+    let envelope: ZkVmProof = bincode::deserialize(&proof).unwrap();
+
+    // fails if the guest commits anything other than ZkVmJournal::encode
+    assert_eq!(
+        ZkVmJournal::decode(&envelope.receipt.journal.bytes),
+        Some(ZkVmJournal {
+            threshold: inputs.threshold,
+            old_state_root: inputs.old_state_root,
+            nonce: inputs.nonce,
+            payload_commitment: commitment().0,
+            new_state_root: next_state_root(&inputs.old_state_root, inputs.nonce, &commitment().0),
+            model_hash: NO_MODEL,
+            dataset_root: NO_DATASET,
+            output: ZkVmOutput { ok: true },
+        })
+    );
+}
+
+#[test]
+fn zkvm_prover_errors_display() {
+    assert_eq!(ZkVmProverError::PolicyViolation.to_string(), "policy violation");
+    assert_eq!(
+        ZkVmProverError::ExecutionFailed("out of cycles".into()).to_string(),
+        "zkVM execution failed: out of cycles"
+    );
+}
+
+#[test]
+fn zkvm_dev_proof_is_rejected_in_production() {
+    let inputs = test_inputs();
+    let proof = prove_dev(5, inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING).unwrap();
+
+    assert!(matches!(
+        ZkVmBackend::new().verify(&proof, &inputs),
+        Err(ProtocolError::InvalidProof)
+    ));
+}
+
+#[test]
+fn zkvm_dev_proof_is_accepted_in_dev_mode() {
+    let inputs = test_inputs();
+    let proof = prove_dev(5, inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING).unwrap();
+
+    let mut engine = VerifierEngine::new(mock_state(&inputs), Box::new(dev_backend()));
+
+    assert!(engine.process_transition(&proof, inputs, next_commitment(&inputs)).is_ok());
+}
+
+#[test]
+fn zkvm_succinct_receipt_is_accepted() {
+    let inputs = test_inputs();
+    let proof = real_proof();
+
+    let envelope: ZkVmProof = bincode::deserialize(proof).unwrap();
+    assert_eq!(envelope.receipt_kind, ReceiptKind::Succinct);
+    assert!(matches!(envelope.receipt.inner, InnerReceipt::Succinct(_)));
+
+    let mut engine = VerifierEngine::new(mock_state(&inputs), Box::new(ZkVmBackend::new()));
+    assert!(engine.process_transition(proof, inputs, next_commitment(&inputs)).is_ok());
+}
+
+#[test]
+fn zkvm_mislabelled_receipt_kind_is_rejected() {
+    let inputs = test_inputs();
+
+    let tampered = tamper(real_proof(), |envelope| envelope.receipt_kind = ReceiptKind::Composite);
+
+    assert!(matches!(
+        ZkVmBackend::new().verify(&tampered, &inputs),
+        Err(ProtocolError::InvalidProof)
+    ));
+}
+
+#[test]
+fn zkvm_prove_report_describes_the_run() {
+    let inputs = test_inputs();
+    let (proof, report) = prove_with_report(
+        5, inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING, dev(),
+    ).unwrap();
+
+    assert_describes_run(&proof, &report);
+}
+
+fn assert_describes_run(proof: &[u8], report: &ProveReport) {
+    assert!(report.segments >= 1);
+    assert!(report.user_cycles > 0);
+    assert!(report.total_cycles >= report.user_cycles + report.paging_cycles);
+    assert!(report.receipt_size > 0 && report.receipt_size < proof.len());
+}
+
+#[test]
+fn zkvm_every_guest_reports_its_run() {
+    let inputs = test_inputs();
+
+    let (proof, report) = prove_model_with_report(
+        FEATURES, model(), 36, inputs.old_state_root, inputs.nonce, BLINDING, dev(),
+    ).unwrap();
+    assert_describes_run(&proof, &report);
+
+    let (proof, report) = prove_membership_with_report(
+        5, &dataset_record(), inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING, dev(),
+    ).unwrap();
+    assert_describes_run(&proof, &report);
+
+    let (proof, report) = prove_chain_with_report(None, 5, 10, BLINDING, dev()).unwrap();
+    assert_describes_run(&proof, &report);
+}
+
+fn model() -> ModelParams {
+    ModelParams { weights: [1, 2, 3, 4, 5, 6, 7, 8] }
+}
+
+/// Scores 1 + 2 + ... + 8 = 36 under `model()`
+const FEATURES: [u32; MODEL_FEATURES] = [1; MODEL_FEATURES];
+
+fn model_inputs(threshold: u64) -> PublicInputs {
+    PublicInputs {
+        threshold,
+        payload_commitment: features_commitment(&FEATURES, &BLINDING),
+        ..test_inputs()
+    }
+}
+
+#[test]
+fn zkvm_model_score_within_threshold_is_accepted() {
+    let inputs = model_inputs(36);
+    let proof = prove_model(
+        FEATURES, model(), inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING,
+        dev(),
+    ).unwrap();
+
+    let backend = dev_backend().with_model(model().hash());
+    let mut engine = VerifierEngine::new(mock_state(&inputs), Box::new(backend));
+    let result = engine.process_transition(&proof, inputs, next_commitment(&inputs));
+
+    assert!(result.is_ok());
+}
+
+#[test]
+fn zkvm_model_score_over_threshold_is_rejected() {
+    let inputs = model_inputs(35);
+    let result = prove_model(
+        FEATURES, model(), inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING,
+        dev(),
+    );
+
+    assert!(matches!(result, Err(ZkVmProverError::PolicyViolation)));
+}
+
+#[test]
+fn zkvm_proof_for_other_model_is_rejected() {
+    let inputs = model_inputs(36);
+    let mut lenient = model();
+    lenient.weights = [0; MODEL_FEATURES];
+    let proof = prove_model(
+        FEATURES, lenient, inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING,
+        dev(),
+    ).unwrap();
+
+    let backend = dev_backend().with_model(model().hash());
+
+    assert!(matches!(
+        backend.verify(&proof, &inputs),
+        Err(ProtocolError::InvalidProof)
+    ));
+}
+
+#[test]
+fn zkvm_score_proof_reports_score_policy() {
+    let inputs = test_inputs();
+    let proof = prove_dev(5, inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING).unwrap();
+
+    assert_eq!(dev_backend().verify_policy(&proof, &inputs).unwrap(), PolicyKind::Score);
+}
+
+#[test]
+fn zkvm_unregistered_program_is_rejected() {
+    let inputs = test_inputs();
+    let proof = prove_dev(5, inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING).unwrap();
+
+    let tampered = tamper(&proof, |envelope| envelope.method_id = Digest::from([7u32; 8]));
+
+    assert!(matches!(
+        dev_backend().verify(&tampered, &inputs),
+        Err(ProtocolError::InvalidProof)
+    ));
+}
+
+/// Record 37 of a dataset whose other nodes are arbitrary
+fn dataset_record() -> DatasetRecord {
+    DatasetRecord {
+        id: 37,
+        salt: [0x5a; 32],
+        path: DatasetPath {
+            siblings: std::array::from_fn(|level| [level as u8 + 1; 32]),
+            index: 37,
+        },
+    }
+}
+
+#[test]
+fn zkvm_membership_proof_reports_membership_policy() {
+    let record = dataset_record();
+    let inputs = PublicInputs {
+        dataset_root: record.root(5),
+        ..test_inputs()
+    };
+    let proof = prove_membership(
+        5, &record, inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING,
+        dev(),
+    ).unwrap();
+
+    assert_eq!(
+        dev_backend().verify_policy(&proof, &inputs).unwrap(),
+        PolicyKind::Membership
+    );
+}
+
+#[test]
+fn zkvm_membership_proof_for_other_dataset_is_rejected() {
+    let record = dataset_record();
+    let inputs = PublicInputs {
+        dataset_root: record.root(6),
+        ..test_inputs()
+    };
+    let proof = prove_membership(
+        5, &record, inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING,
+        dev(),
+    ).unwrap();
+
+    assert!(matches!(
+        dev_backend().verify(&proof, &inputs),
+        Err(ProtocolError::InvalidProof)
+    ));
+}
+
+#[test]
+fn zkvm_membership_proof_for_other_record_is_rejected() {
+    // same score and position, but another record's id and salt
+    let record = dataset_record();
+    let inputs = PublicInputs {
+        dataset_root: record.root(5),
+        ..test_inputs()
+    };
+    let other = DatasetRecord { id: 38, salt: [0xa5; 32], ..record };
+    let proof = prove_membership(
+        5, &other, inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING,
+        dev(),
+    ).unwrap();
+
+    assert!(matches!(
+        dev_backend().verify(&proof, &inputs),
+        Err(ProtocolError::InvalidProof)
+    ));
+}
+
+#[test]
+fn zkvm_score_proof_claiming_a_dataset_is_rejected() {
+    // the score guest commits NO_DATASET; it proves nothing about this root
+    let inputs = PublicInputs {
+        dataset_root: dataset_record().root(5),
+        ..test_inputs()
+    };
+    let proof = prove_dev(5, inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING).unwrap();
+
+    assert!(matches!(
+        dev_backend().verify(&proof, &inputs),
+        Err(ProtocolError::InvalidProof)
+    ));
+}
+
+#[test]
+fn zkvm_dataset_policy_rejects_score_proof() {
+    let inputs = test_inputs();
+    let proof = prove_dev(5, inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING).unwrap();
+    let kind = dev_backend().verify(&proof, &inputs).unwrap();
+
+    assert_eq!(kind, PolicyKind::Score);
+    assert!(matches!(
+        Policy::Dataset(NO_DATASET).enforce(&inputs, kind),
+        Err(ProtocolError::PolicyViolation)
+    ));
+}
+
+#[test]
+fn zkvm_chain_attests_current_state() {
+    let first = prove_chain(None, 5, 10, BLINDING, dev()).unwrap();
+    let second = prove_chain(Some(&first), 3, 10, BLINDING, dev()).unwrap();
+
+    let root1 = next_state_root(&[0u8; 32], 1, &score_commitment(5, &BLINDING));
+    let root2 = next_state_root(&root1, 2, &score_commitment(3, &BLINDING));
+    let after = |state_root, nonce| ProtocolState { state_root, nonce, ..ProtocolState::genesis() };
+
+    let backend = dev_backend();
+    let policies = PolicySet::default();
+    assert!(backend.verify_chain(&second, &after(root2, 2), &policies).is_ok());
+
+    // the latest link only attests the latest state
+    assert!(matches!(
+        backend.verify_chain(&second, &after(root1, 1), &policies),
+        Err(ProtocolError::InvalidProof)
+    ));
+}
+
+#[test]
+fn zkvm_chain_under_looser_threshold_is_rejected() {
+    // score 15 only passes because the chain was proven at threshold 20
+    let first = prove_chain(None, 15, 20, BLINDING, dev()).unwrap();
+
+    let state = ProtocolState {
+        state_root: next_state_root(&[0u8; 32], 1, &score_commitment(15, &BLINDING)),
+        nonce: 1,
+        ..ProtocolState::genesis()
+    };
+    let policies = PolicySet(vec![Policy::Phase1, Policy::MaxThreshold(10)]);
+
+    assert!(matches!(
+        dev_backend().verify_chain(&first, &state, &policies),
+        Err(ProtocolError::PolicyViolation)
+    ));
+}
+
+#[test]
+fn zkvm_chain_with_failing_link_is_rejected() {
+    let first = prove_chain(None, 5, 10, BLINDING, dev()).unwrap();
+    let result = prove_chain(Some(&first), 20, 10, BLINDING, dev());
+
+    assert!(matches!(result, Err(ZkVmProverError::PolicyViolation)));
+}
+
+#[test]
+fn zkvm_transition_proof_is_not_a_chain() {
+    let inputs = test_inputs();
+    let proof = prove_dev(5, inputs.threshold, inputs.old_state_root, inputs.nonce, BLINDING).unwrap();
+
+    let state = ProtocolState {
+        state_root: next_state_root(&inputs.old_state_root, inputs.nonce, &commitment().0),
+        nonce: inputs.nonce,
+        ..ProtocolState::genesis()
+    };
+
+    assert!(matches!(
+        dev_backend().verify_chain(&proof, &state, &PolicySet::default()),
+        Err(ProtocolError::InvalidProof)
+    ));
 }
@@ -0,0 +1,21 @@
+#![no_std]
+#![no_main]
+
+use risc0_zkvm::{guest::env, sha::Digest};
+use zkcg_common::types::ChainInput;
+
+risc0_zkvm::guest::entry!(main);
+
+fn main() {
+    let input: ChainInput = env::read();
+
+    // 🔐 The previous link must itself be a receipt of this program;
+    // resolved as an assumption, so the final receipt covers the chain
+    if let Some(previous) = &input.previous {
+        env::verify(Digest::from(input.image_id), &previous.encode()[..])
+            .expect("previous link verified");
+    }
+
+    // 🔐 Apply the next transition on top of the previous state
+    env::commit_slice(&input.journal().encode());
+}
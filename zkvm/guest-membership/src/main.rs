@@ -0,0 +1,23 @@
+#![no_std]
+#![no_main]
+
+use risc0_zkvm::guest::env;
+use zkcg_common::{
+    dataset::DatasetRecord,
+    types::ZkVmInput,
+};
+
+risc0_zkvm::guest::entry!(main);
+
+fn main() {
+    let input: ZkVmInput = env::read();
+    let record: DatasetRecord = env::read();
+
+    // 🔐 Same score rule and transition as the score guest
+    let mut journal = input.journal();
+
+    // 🔐 ...for a record that is a leaf of the committed dataset
+    journal.dataset_root = record.root(input.score.score());
+
+    env::commit_slice(&journal.encode());
+}
@@ -5,8 +5,7 @@ fn main() {
         return;
     }
 
-    // RISC0 0.21 expects a map of guest-name -> GuestOptions
-   
-
+    // Builds every guest listed under [package.metadata.risc0] in
+    // Cargo.toml; each gets its own <NAME>_ELF / <NAME>_ID in methods.rs
     risc0_build::embed_methods();
 }
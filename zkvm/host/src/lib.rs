@@ -5,60 +5,356 @@ mod methods {
 
 use risc0_zkp::core::digest::Digest;
 use serde::{Serialize, Deserialize};
+use std::time::Duration;
+#[cfg(feature = "zk-vm")]
+use std::time::Instant;
+use thiserror::Error;
 
 
-use risc0_zkvm::{ExecutorEnv, default_prover};
-use risc0_binfmt::Digestible;
-use zkcg_common::types::ZkVmInput;
+use risc0_zkvm::Receipt;
+#[cfg(feature = "zk-vm")]
+use risc0_zkvm::{ExecutorEnv, ProverOpts, default_executor, default_prover};
+#[cfg(feature = "zk-vm")]
+use zkcg_common::{
+    dataset::DatasetRecord,
+    state::ProtocolState,
+    types::{
+        ChainInput, ChainJournal, ModelParams, ScoreInput, ZkVmInput, ZkVmJournal, MODEL_FEATURES,
+    },
+};
 
-#[derive(Debug)]
+/// Why `prove` produced no proof
+#[derive(Debug, Error)]
 pub enum ZkVmProverError {
+    /// The guest ran to completion and rejected the submission
+    #[error("policy violation")]
     PolicyViolation,
-    ExecutionFailed,
+
+    /// The executor environment could not be built from the input
+    #[error("failed to build zkVM environment: {0}")]
+    Environment(String),
+
+    /// The executor or the prover failed
+    #[error("zkVM execution failed: {0}")]
+    ExecutionFailed(String),
+
+    /// The guest committed something other than a `ZkVmJournal`
+    #[error("malformed zkVM journal")]
+    MalformedJournal,
+
+    /// A proof envelope could not be encoded or decoded
+    #[error("failed to serialize proof: {0}")]
+    Serialization(#[from] bincode::Error),
+}
+
+/// Receipt flavour produced by the prover
+///
+/// Composite receipts are one STARK per segment: fastest to prove,
+/// largest to ship. Succinct receipts compress them into a single
+/// constant-size STARK at the cost of extra recursion work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReceiptKind {
+    #[default]
+    Composite,
+    Succinct,
+}
+
+/// Per-deployment proving choices
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProveOptions {
+    pub receipt_kind: ReceiptKind,
+    /// Execute without proving and return a fake receipt, as `prove_dev`
+    /// does; `receipt_kind` is then only recorded in the envelope
+    pub dev_mode: bool,
+}
+
+/// Cost of one proving run, for sizing hardware and benchmarks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProveReport {
+    /// All cycles, including paging and reserved ones
+    pub total_cycles: u64,
+    /// Cycles spent executing guest code
+    pub user_cycles: u64,
+    pub paging_cycles: u64,
+    pub reserved_cycles: u64,
+    pub segments: usize,
+    /// Wall-clock time of execution plus proving
+    pub duration: Duration,
+    /// Serialized size of the receipt, in bytes
+    pub receipt_size: usize,
 }
 
+#[cfg(feature = "zk-vm")]
 pub fn prove(score: u64, threshold: u64, old_state_root: [u8; 32],
-    nonce: u64) -> Result<Vec<u8>, ZkVmProverError> {
-    let result =std::panic::catch_unwind(|| {let mut builder = ExecutorEnv::builder();
-            builder
-                .write(&ZkVmInput { score, threshold, old_state_root,
-                nonce })
-                .expect("failed to write zkVM input");
+    nonce: u64, blinding: [u8; 32], options: ProveOptions) -> Result<Vec<u8>, ZkVmProverError> {
+    prove_with_report(score, threshold, old_state_root, nonce, blinding, options)
+        .map(|(proof, _)| proof)
+}
 
-            let env = builder.build().expect("failed to build executor env");
+/// `prove`, also returning the execution statistics of the run
+#[cfg(feature = "zk-vm")]
+pub fn prove_with_report(score: u64, threshold: u64, old_state_root: [u8; 32],
+    nonce: u64, blinding: [u8; 32], options: ProveOptions)
+    -> Result<(Vec<u8>, ProveReport), ZkVmProverError> {
+    let input = ZkVmInput { score: ScoreInput::Plain(score), threshold, old_state_root, nonce, blinding };
+    prove_with_opts(GuestProgram::Score, || executor_env(&input, None), &prover_opts(options),
+        options.receipt_kind)
+}
 
-            let prove_info = default_prover()
-                .prove(env, elf())
-                .expect("zkVM execution failed");
+/// Prove a score the guest computes itself from private `features`
+///
+/// The journal commits `params.hash()`, and the payload commitment is
+/// `features_commitment(features, blinding)`.
+#[cfg(feature = "zk-vm")]
+pub fn prove_model(features: [u32; MODEL_FEATURES], params: ModelParams, threshold: u64,
+    old_state_root: [u8; 32], nonce: u64, blinding: [u8; 32],
+    options: ProveOptions) -> Result<Vec<u8>, ZkVmProverError> {
+    prove_model_with_report(features, params, threshold, old_state_root, nonce, blinding, options)
+        .map(|(proof, _)| proof)
+}
 
-            let receipt = prove_info.receipt;
+/// `prove_model`, also returning the execution statistics of the run
+#[cfg(feature = "zk-vm")]
+pub fn prove_model_with_report(features: [u32; MODEL_FEATURES], params: ModelParams,
+    threshold: u64, old_state_root: [u8; 32], nonce: u64, blinding: [u8; 32],
+    options: ProveOptions) -> Result<(Vec<u8>, ProveReport), ZkVmProverError> {
+    let input = ZkVmInput {
+        score: ScoreInput::Model { features, params },
+        threshold,
+        old_state_root,
+        nonce,
+        blinding,
+    };
+    prove_with_opts(GuestProgram::Score, || executor_env(&input, None), &prover_opts(options),
+        options.receipt_kind)
+}
 
-            let proof = ZkVmProof {
-                method_id: method_id(),
-                journal_digest: receipt.journal.digest::<risc0_zkvm::sha::Impl>(),
-            };
+/// Prove `score` for `record` in a dataset
+///
+/// Runs the membership guest, whose journal commits the dataset root
+/// `record.root(score)`.
+#[cfg(feature = "zk-vm")]
+pub fn prove_membership(score: u64, record: &DatasetRecord, threshold: u64,
+    old_state_root: [u8; 32], nonce: u64, blinding: [u8; 32],
+    options: ProveOptions) -> Result<Vec<u8>, ZkVmProverError> {
+    prove_membership_with_report(score, record, threshold, old_state_root, nonce, blinding, options)
+        .map(|(proof, _)| proof)
+}
 
-            bincode::serialize(&proof).expect("failed to serialize proof")});
+/// `prove_membership`, also returning the execution statistics of the run
+#[cfg(feature = "zk-vm")]
+pub fn prove_membership_with_report(score: u64, record: &DatasetRecord, threshold: u64,
+    old_state_root: [u8; 32], nonce: u64, blinding: [u8; 32],
+    options: ProveOptions) -> Result<(Vec<u8>, ProveReport), ZkVmProverError> {
+    let input = ZkVmInput { score: ScoreInput::Plain(score), threshold, old_state_root, nonce, blinding };
+    prove_with_opts(GuestProgram::Membership, || executor_env(&input, Some(record)),
+        &prover_opts(options), options.receipt_kind)
+}
 
-            match result {
-        Ok(bytes) => Ok(bytes),
-        Err(_) => Err(ZkVmProverError::PolicyViolation),
+#[cfg(feature = "zk-vm")]
+fn prover_opts(options: ProveOptions) -> ProverOpts {
+    // fake receipts only when asked for, whatever RISC0_DEV_MODE says
+    let opts = ProverOpts::default().with_dev_mode(options.dev_mode);
+    match options.receipt_kind {
+        ReceiptKind::Composite => opts.with_receipt_kind(risc0_zkvm::ReceiptKind::Composite),
+        ReceiptKind::Succinct => opts.with_receipt_kind(risc0_zkvm::ReceiptKind::Succinct),
     }
 }
 
-/// Opaque proof envelope produced by the prover
+/// Execute the guest without proving and wrap a fake receipt
+///
+/// Fast enough for tests, but attests nothing: only a `ZkVmBackend`
+/// with dev mode enabled accepts the result.
+#[cfg(feature = "zk-vm")]
+pub fn prove_dev(score: u64, threshold: u64, old_state_root: [u8; 32],
+    nonce: u64, blinding: [u8; 32]) -> Result<Vec<u8>, ZkVmProverError> {
+    let options = ProveOptions { dev_mode: true, ..ProveOptions::default() };
+    prove(score, threshold, old_state_root, nonce, blinding, options)
+}
+
+/// Extend a chain of transitions by one link
+///
+/// `previous` is the proof of the last link from `prove_chain`, or `None`
+/// to start from genesis; the transition applies `score` on top of its
+/// state. Every link must use the threshold of the first one, which the
+/// journal commits. The previous receipt is proven as an assumption, so
+/// the result attests the whole chain. Succinct receipts keep its size constant;
+/// composite ones carry every earlier receipt.
+#[cfg(feature = "zk-vm")]
+pub fn prove_chain(previous: Option<&[u8]>, score: u64, threshold: u64, blinding: [u8; 32],
+    options: ProveOptions) -> Result<Vec<u8>, ZkVmProverError> {
+    prove_chain_with_report(previous, score, threshold, blinding, options)
+        .map(|(proof, _)| proof)
+}
+
+/// `prove_chain`, also returning the execution statistics of the run
+#[cfg(feature = "zk-vm")]
+pub fn prove_chain_with_report(previous: Option<&[u8]>, score: u64, threshold: u64,
+    blinding: [u8; 32], options: ProveOptions)
+    -> Result<(Vec<u8>, ProveReport), ZkVmProverError> {
+    let previous = previous
+        .map(|bytes| -> Result<_, ZkVmProverError> {
+            let proof: ZkVmProof = bincode::deserialize(bytes)?;
+            let journal = ChainJournal::decode(&proof.receipt.journal.bytes)
+                .ok_or(ZkVmProverError::MalformedJournal)?;
+            Ok((proof.receipt, journal))
+        })
+        .transpose()?;
+
+    let genesis = ProtocolState::genesis();
+    let (old_state_root, nonce) = previous
+        .as_ref()
+        .map_or((genesis.state_root, genesis.nonce), |(_, j)| (j.state_root, j.nonce));
+
+    let image_id = GuestProgram::Chain.method_id();
+    let input = ChainInput {
+        image_id: image_id.as_words().try_into().expect("digest is 8 words"),
+        previous: previous.as_ref().map(|(_, journal)| journal.clone()),
+        transition: ZkVmInput {
+            score: ScoreInput::Plain(score),
+            threshold,
+            old_state_root,
+            nonce: nonce + 1,
+            blinding,
+        },
+    };
+
+    let env = || {
+        let mut builder = ExecutorEnv::builder();
+        builder
+            .write(&input)
+            .map_err(|e| ZkVmProverError::Environment(e.to_string()))?;
+        if let Some((receipt, _)) = &previous {
+            builder.add_assumption(receipt.clone());
+        }
+        builder
+            .build()
+            .map_err(|e| ZkVmProverError::Environment(e.to_string()))
+    };
+
+    prove_with_opts(GuestProgram::Chain, env, &prover_opts(options), options.receipt_kind)
+}
+
+/// Guest input: the `ZkVmInput`, then the dataset record if the guest reads one
+#[cfg(feature = "zk-vm")]
+fn executor_env(input: &ZkVmInput,
+    record: Option<&DatasetRecord>) -> Result<ExecutorEnv<'static>, ZkVmProverError> {
+    let mut builder = ExecutorEnv::builder();
+    builder
+        .write(input)
+        .map_err(|e| ZkVmProverError::Environment(e.to_string()))?;
+    if let Some(record) = record {
+        builder
+            .write(record)
+            .map_err(|e| ZkVmProverError::Environment(e.to_string()))?;
+    }
+    builder
+        .build()
+        .map_err(|e| ZkVmProverError::Environment(e.to_string()))
+}
+
+/// Execute `program`, then prove it if the guest accepted the submission
+///
+/// `env` builds the guest environment, once for each run. A rejected
+/// submission costs an execution only, never a proof.
+#[cfg(feature = "zk-vm")]
+fn prove_with_opts(program: GuestProgram,
+    env: impl Fn() -> Result<ExecutorEnv<'static>, ZkVmProverError>, opts: &ProverOpts,
+    receipt_kind: ReceiptKind) -> Result<(Vec<u8>, ProveReport), ZkVmProverError> {
+    let started = Instant::now();
+    let session = default_executor()
+        .execute(env()?, program.elf())
+        .map_err(|e| ZkVmProverError::ExecutionFailed(e.to_string()))?;
+
+    // the guest reports its policy verdict in the journal
+    let journal = &session.journal.bytes;
+    let ok = match program {
+        GuestProgram::Chain => ChainJournal::decode(journal).map(|j| j.output.ok),
+        _ => ZkVmJournal::decode(journal).map(|j| j.output.ok),
+    }
+    .ok_or(ZkVmProverError::MalformedJournal)?;
+    if !ok {
+        return Err(ZkVmProverError::PolicyViolation);
+    }
+
+    let prove_info = default_prover()
+        .prove_with_opts(env()?, program.elf(), opts)
+        .map_err(|e| ZkVmProverError::ExecutionFailed(e.to_string()))?;
+    let duration = started.elapsed();
+
+    let stats = &prove_info.stats;
+    let report = ProveReport {
+        total_cycles: stats.total_cycles,
+        user_cycles: stats.user_cycles,
+        paging_cycles: stats.paging_cycles,
+        reserved_cycles: stats.reserved_cycles,
+        segments: stats.segments,
+        duration,
+        receipt_size: bincode::serialized_size(&prove_info.receipt)? as usize,
+    };
+
+    let proof = ZkVmProof {
+        method_id: program.method_id(),
+        receipt_kind,
+        receipt: prove_info.receipt,
+    };
+
+    Ok((bincode::serialize(&proof)?, report))
+}
+
+/// Proof envelope produced by the prover
+///
+/// Carries the full receipt (seal + journal), so the verifier can check
+/// it cryptographically against `method_id`. `receipt_kind` records
+/// the flavour requested from the prover.
 #[derive(Serialize, Deserialize)]
 pub struct ZkVmProof {
     pub method_id: Digest,
-    pub journal_digest: Digest,
+    pub receipt_kind: ReceiptKind,
+    pub receipt: Receipt,
+}
+
+/// Guest programs built by build.rs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuestProgram {
+    /// Score (host-supplied or model-computed) against the threshold
+    Score,
+    /// Score check for a record proven to be in a committed dataset
+    Membership,
+    /// One transition on top of a verified chain from genesis
+    Chain,
+}
+
+impl GuestProgram {
+    pub const ALL: [Self; 3] = [Self::Score, Self::Membership, Self::Chain];
+
+    /// Image ID a receipt of this program verifies against
+    #[cfg(feature = "zk-vm")]
+    pub fn method_id(self) -> Digest {
+        match self {
+            Self::Score => Digest::from(methods::ZKCG_ZKVM_GUEST_ID),
+            Self::Membership => Digest::from(methods::ZKCG_ZKVM_MEMBERSHIP_GUEST_ID),
+            Self::Chain => Digest::from(methods::ZKCG_ZKVM_CHAIN_GUEST_ID),
+        }
+    }
+
+    #[cfg(feature = "zk-vm")]
+    pub fn elf(self) -> &'static [u8] {
+        match self {
+            Self::Score => methods::ZKCG_ZKVM_GUEST_ELF,
+            Self::Membership => methods::ZKCG_ZKVM_MEMBERSHIP_GUEST_ELF,
+            Self::Chain => methods::ZKCG_ZKVM_CHAIN_GUEST_ELF,
+        }
+    }
 }
 
+/// Image ID of the score guest
 #[cfg(feature = "zk-vm")]
 pub fn method_id() -> Digest {
-    Digest::from(methods::ZKCG_ZKVM_GUEST_ID)
+    GuestProgram::Score.method_id()
 }
 
 #[cfg(feature = "zk-vm")]
 pub fn elf() -> &'static [u8] {
-    methods::ZKCG_ZKVM_GUEST_ELF
+    GuestProgram::Score.elf()
 }